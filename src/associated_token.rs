@@ -0,0 +1,74 @@
+use anyhow::{bail, Result};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Associated Token Account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// SPL Token program
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Token-2022 (token extensions) program
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Token program that owns the mint, given as `spl-token`, `token-2022` or a program ID
+#[derive(Clone, Copy)]
+pub struct TokenProgram(pub Pubkey);
+
+impl FromStr for TokenProgram {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(TokenProgram(match s {
+            "spl-token" => TOKEN_PROGRAM_ID,
+            "token-2022" => TOKEN_2022_PROGRAM_ID,
+            _ => Pubkey::from_str(s)?,
+        }))
+    }
+}
+
+/// Which address the patterns are checked against
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchTarget {
+    Ata,
+    Owner,
+    Both,
+}
+
+impl FromStr for MatchTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ata" => Ok(MatchTarget::Ata),
+            "owner" => Ok(MatchTarget::Owner),
+            "both" => Ok(MatchTarget::Both),
+            _ => bail!("Match target must be ata, owner or both"),
+        }
+    }
+}
+
+/// Mint and token program whose associated token account is derived for every owner
+#[derive(Clone)]
+pub struct AssociatedToken {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub target: MatchTarget,
+}
+
+impl AssociatedToken {
+    /// Same derivation as `get_associated_token_address_with_program_id`
+    pub fn address(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                owner.as_ref(),
+                self.token_program.as_ref(),
+                self.mint.as_ref(),
+            ],
+            &ASSOCIATED_TOKEN_PROGRAM_ID,
+        )
+        .0
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::{anyhow, bail, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+use std::str::FromStr;
+
+/// Constraint on the raw public key bytes: `key & mask == value`
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct ByteMask {
+    /// Expected bits, already masked
+    pub value: [u8; 32],
+    pub mask: [u8; 32],
+}
+
+impl ByteMask {
+    /// Parses a hex prefix such as `00` or `a` (one nibble) and an optional hex mask.
+    /// Without a mask every nibble of the prefix has to match.
+    pub fn parse(prefix: &str, mask: Option<&str>) -> Result<Self> {
+        let value = parse_hex(prefix)?;
+        let mask = match mask {
+            Some(mask) => parse_hex(mask)?,
+            None => {
+                let mut mask = [0u8; 32];
+                for nibble in 0..prefix.len() {
+                    mask[nibble / 2] |= if nibble % 2 == 0 { 0xf0 } else { 0x0f };
+                }
+                mask
+            }
+        };
+        if value
+            .iter()
+            .zip(&mask)
+            .any(|(value, mask)| value & !mask != 0)
+        {
+            bail!("Byte prefix {} sets bits outside the mask", prefix);
+        }
+        if mask.iter().all(|&byte| byte == 0) {
+            bail!("Byte mask selects no bits");
+        }
+        Ok(ByteMask { value, mask })
+    }
+
+    pub fn matches(&self, key: &[u8; 32]) -> bool {
+        key.iter()
+            .zip(&self.mask)
+            .zip(&self.value)
+            .all(|((key, mask), value)| key & mask == *value)
+    }
+
+    /// Number of constrained bits, the difficulty in bits
+    pub fn bits(&self) -> u32 {
+        self.mask.iter().map(|byte| byte.count_ones()).sum()
+    }
+
+    pub fn probability(&self) -> f64 {
+        0.5f64.powi(self.bits() as i32)
+    }
+}
+
+impl fmt::Display for ByteMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let used = self
+            .mask
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .map_or(0, |i| i + 1);
+        write!(
+            f,
+            "bytes {}/{} ({} bits)",
+            hex::encode(&self.value[..used]),
+            hex::encode(&self.mask[..used]),
+            self.bits()
+        )
+    }
+}
+
+/// Big-endian hex of up to 32 bytes, left-aligned and zero-padded; odd lengths end in a nibble
+fn parse_hex(text: &str) -> Result<[u8; 32]> {
+    if text.is_empty() || text.len() > 64 {
+        bail!("Hex '{}' must have 1 to 64 digits", text);
+    }
+    let mut bytes = [0u8; 32];
+    for (i, c) in text.chars().enumerate() {
+        let Some(nibble) = c.to_digit(16) else {
+            bail!("'{}' in '{}' is not a hex digit", c, text);
+        };
+        bytes[i / 2] |= (nibble as u8) << if i % 2 == 0 { 4 } else { 0 };
+    }
+    Ok(bytes)
+}
+
+/// Half-open interval of raw keys compared as big-endian 256-bit integers, which is the
+/// order programs get from sorting `Pubkey`s. 33 bytes so the end can be 2^256.
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyRange {
+    start: [u8; 33],
+    end: [u8; 33],
+}
+
+impl Default for KeyRange {
+    fn default() -> Self {
+        let mut end = [0u8; 33];
+        end[0] = 1;
+        KeyRange {
+            start: [0u8; 33],
+            end,
+        }
+    }
+}
+
+impl KeyRange {
+    /// Keys sorting strictly before `bound`
+    pub fn less_than(bound: &[u8; 32]) -> Self {
+        KeyRange {
+            end: widen(bound),
+            ..Default::default()
+        }
+    }
+
+    /// Keys sorting strictly after `bound`
+    pub fn greater_than(bound: &[u8; 32]) -> Self {
+        let mut start = widen(bound);
+        increment(&mut start);
+        KeyRange {
+            start,
+            ..Default::default()
+        }
+    }
+
+    /// `start..end` of pubkeys or hex prefixes, where a hex prefix is padded with zeros
+    pub fn parse(text: &str) -> Result<Self> {
+        let Some((start, end)) = text.split_once("..") else {
+            bail!("Key range '{}' must look like <start>..<end>", text);
+        };
+        let mut range = KeyRange::default();
+        if !start.is_empty() {
+            range.start = widen(&parse_bound(start)?);
+        }
+        if !end.is_empty() {
+            range.end = widen(&parse_bound(end)?);
+        }
+        Ok(range)
+    }
+
+    /// Keys in both ranges
+    pub fn intersect(&self, other: &KeyRange) -> KeyRange {
+        KeyRange {
+            start: self.start.max(other.start),
+            end: self.end.min(other.end),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    pub fn matches(&self, key: &[u8; 32]) -> bool {
+        let value = widen(key);
+        self.start <= value && value < self.end
+    }
+
+    /// Exact share of the keyspace, computed from the width so tiny ranges keep their precision
+    pub fn probability(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let mut width = [0u8; 33];
+        let mut borrow = 0i16;
+        for i in (0..33).rev() {
+            let difference = self.end[i] as i16 - self.start[i] as i16 - borrow;
+            borrow = (difference < 0) as i16;
+            width[i] = difference.rem_euclid(256) as u8;
+        }
+        width
+            .iter()
+            .fold(0.0, |value, &byte| value * 256.0 + byte as f64)
+            / 2f64.powi(256)
+    }
+}
+
+impl fmt::Display for KeyRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Open ends are left empty, as in the `--key-range` syntax
+        let bound = |value: &[u8; 33]| match value {
+            [1, ..] => String::new(),
+            value if value.iter().all(|&byte| byte == 0) => String::new(),
+            value => Pubkey::new_from_array(value[1..].try_into().unwrap()).to_string(),
+        };
+        write!(
+            f,
+            "keys {}..{} ({:.1} bits)",
+            bound(&self.start),
+            bound(&self.end),
+            -self.probability().log2()
+        )
+    }
+}
+
+/// A base58 pubkey, or a hex prefix padded with zeros
+fn parse_bound(text: &str) -> Result<[u8; 32]> {
+    match Pubkey::from_str(text) {
+        Ok(pubkey) => Ok(pubkey.to_bytes()),
+        Err(_) => parse_hex(text.trim_start_matches("0x"))
+            .map_err(|_| anyhow!("'{}' is neither a pubkey nor a hex prefix", text)),
+    }
+}
+
+fn widen(key: &[u8; 32]) -> [u8; 33] {
+    let mut value = [0u8; 33];
+    value[1..].copy_from_slice(key);
+    value
+}
+
+fn increment(value: &mut [u8; 33]) {
+    for byte in value.iter_mut().rev() {
+        let (next, overflow) = byte.overflowing_add(1);
+        *byte = next;
+        if !overflow {
+            break;
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{bail, Result};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use crc::{Crc, CRC_16_XMODEM};
+use sha3::Sha3_256;
+use std::str::FromStr;
+
+use crate::pattern::{CharSet, BASE58_ALPHABET};
+
+const HEX_ALPHABET: &[u8] = b"0123456789abcdef";
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// How a chain turns an Ed25519 public key into the address users see, and what a
+/// random address looks like to the pattern estimator
+pub trait AddressFormat: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Full display address of the public key
+    fn address(&self, public_key: &[u8; 32]) -> String;
+
+    /// Text every address starts with, which patterns skip (e.g. `0x`)
+    fn fixed_prefix(&self) -> &'static str {
+        ""
+    }
+
+    /// Characters that can follow the fixed prefix
+    fn alphabet(&self) -> &'static [u8];
+
+    /// Longest address after the fixed prefix
+    fn max_len(&self) -> usize;
+
+    /// Chance that the character at `position` after the fixed prefix is in `set`,
+    /// or at an unknown position if None
+    fn char_probability(&self, _position: Option<usize>, set: &CharSet) -> f64 {
+        set.len() as f64 / self.alphabet().len() as f64
+    }
+
+    /// Whether the address is the base58 key itself, so the numeric fast paths apply
+    fn is_solana(&self) -> bool {
+        false
+    }
+
+    /// Address without the fixed prefix, which is what patterns are matched against
+    fn body(&self, public_key: &[u8; 32]) -> String {
+        let mut address = self.address(public_key);
+        address.drain(..self.fixed_prefix().len());
+        address
+    }
+}
+
+/// Base58 public key
+pub struct Solana;
+
+impl AddressFormat for Solana {
+    fn name(&self) -> &'static str {
+        "solana"
+    }
+
+    fn address(&self, public_key: &[u8; 32]) -> String {
+        bs58::encode(public_key).into_string()
+    }
+
+    fn alphabet(&self) -> &'static [u8] {
+        BASE58_ALPHABET
+    }
+
+    fn max_len(&self) -> usize {
+        44
+    }
+
+    fn is_solana(&self) -> bool {
+        true
+    }
+}
+
+/// `0x` and the hex SHA3-256 of the key followed by the Ed25519 scheme byte 0x00
+pub struct Aptos;
+
+impl AddressFormat for Aptos {
+    fn name(&self) -> &'static str {
+        "aptos"
+    }
+
+    fn address(&self, public_key: &[u8; 32]) -> String {
+        let mut hasher = Sha3_256::new();
+        hasher.update(public_key);
+        hasher.update([0x00]);
+        format!("0x{}", hex::encode(hasher.finalize()))
+    }
+
+    fn fixed_prefix(&self) -> &'static str {
+        "0x"
+    }
+
+    fn alphabet(&self) -> &'static [u8] {
+        HEX_ALPHABET
+    }
+
+    fn max_len(&self) -> usize {
+        64
+    }
+}
+
+/// `0x` and the hex BLAKE2b-256 of the Ed25519 flag byte 0x00 followed by the key
+pub struct Sui;
+
+impl AddressFormat for Sui {
+    fn name(&self) -> &'static str {
+        "sui"
+    }
+
+    fn address(&self, public_key: &[u8; 32]) -> String {
+        let mut hasher = Blake2b::<U32>::new();
+        hasher.update([0x00]);
+        hasher.update(public_key);
+        format!("0x{}", hex::encode(hasher.finalize()))
+    }
+
+    fn fixed_prefix(&self) -> &'static str {
+        "0x"
+    }
+
+    fn alphabet(&self) -> &'static [u8] {
+        HEX_ALPHABET
+    }
+
+    fn max_len(&self) -> usize {
+        64
+    }
+}
+
+/// Implicit account: the lowercase hex of the key itself
+pub struct Near;
+
+impl AddressFormat for Near {
+    fn name(&self) -> &'static str {
+        "near"
+    }
+
+    fn address(&self, public_key: &[u8; 32]) -> String {
+        hex::encode(public_key)
+    }
+
+    fn alphabet(&self) -> &'static [u8] {
+        HEX_ALPHABET
+    }
+
+    fn max_len(&self) -> usize {
+        64
+    }
+}
+
+/// StrKey account ID: base32 of the version byte, the key and a CRC16-XModem checksum
+pub struct Stellar;
+
+impl AddressFormat for Stellar {
+    fn name(&self) -> &'static str {
+        "stellar"
+    }
+
+    fn address(&self, public_key: &[u8; 32]) -> String {
+        let mut data = Vec::with_capacity(35);
+        data.push(6 << 3); // Account ID version byte, always encodes to a leading 'G'
+        data.extend_from_slice(public_key);
+        let checksum = Crc::<u16>::new(&CRC_16_XMODEM).checksum(&data);
+        data.extend_from_slice(&checksum.to_le_bytes());
+        base32::encode(base32::Alphabet::RFC4648 { padding: false }, &data)
+    }
+
+    fn fixed_prefix(&self) -> &'static str {
+        "G"
+    }
+
+    fn alphabet(&self) -> &'static [u8] {
+        BASE32_ALPHABET
+    }
+
+    fn max_len(&self) -> usize {
+        55
+    }
+
+    /// The character after the `G` carries the top two key bits, so it is one of A-D
+    fn char_probability(&self, position: Option<usize>, set: &CharSet) -> f64 {
+        match position {
+            Some(0) => b"ABCD".iter().filter(|&&c| set.contains(c)).count() as f64 / 4.0,
+            _ => set.len() as f64 / BASE32_ALPHABET.len() as f64,
+        }
+    }
+}
+
+/// `--chain` choice
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Chain {
+    Solana,
+    Aptos,
+    Sui,
+    Near,
+    Stellar,
+}
+
+impl Chain {
+    pub fn format(&self) -> &'static dyn AddressFormat {
+        match self {
+            Chain::Solana => &Solana,
+            Chain::Aptos => &Aptos,
+            Chain::Sui => &Sui,
+            Chain::Near => &Near,
+            Chain::Stellar => &Stellar,
+        }
+    }
+}
+
+impl FromStr for Chain {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "solana" => Ok(Chain::Solana),
+            "aptos" => Ok(Chain::Aptos),
+            "sui" => Ok(Chain::Sui),
+            "near" => Ok(Chain::Near),
+            "stellar" => Ok(Chain::Stellar),
+            _ => bail!("Chain must be solana, aptos, sui, near or stellar"),
+        }
+    }
+}
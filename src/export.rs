@@ -0,0 +1,203 @@
+use anyhow::{anyhow, bail, Result};
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+use crate::shamir::{split, ShamirScheme};
+use crate::utils::{derive_hd_keypair, write_private_file, VanityResult};
+
+/// How found keys are written out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// Pretty JSON array of results, the format searches resume from
+    Json,
+    /// `<pubkey>.json` with the `[u8; 64]` array that `solana-keygen grind` writes
+    Keypair,
+    /// `<pubkey>.b58` with the base58 secret that Phantom and Solflare import
+    Base58,
+    /// `<pubkey>.hex` with the hex of the 64-byte secret
+    Hex,
+    /// `<pubkey>.bin` with the raw 64 bytes
+    Binary,
+    /// One JSON result per line
+    Ndjson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "keypair" => Ok(ExportFormat::Keypair),
+            "base58" => Ok(ExportFormat::Base58),
+            "hex" => Ok(ExportFormat::Hex),
+            "binary" => Ok(ExportFormat::Binary),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            _ => bail!("Format must be json, keypair, base58, hex, binary or ndjson"),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// File extension of the per-key formats, which write one `<pubkey>` file each
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            ExportFormat::Keypair => Some("json"),
+            ExportFormat::Base58 => Some("b58"),
+            ExportFormat::Hex => Some("hex"),
+            ExportFormat::Binary => Some("bin"),
+            ExportFormat::Json | ExportFormat::Ndjson => None,
+        }
+    }
+
+    fn encode(&self, keypair: &Keypair) -> Result<Zeroizing<Vec<u8>>> {
+        let bytes = Zeroizing::new(keypair.to_bytes());
+        Ok(Zeroizing::new(match self {
+            ExportFormat::Keypair => {
+                serde_json::to_string(&*Zeroizing::new(bytes.to_vec()))?.into_bytes()
+            }
+            ExportFormat::Base58 => {
+                format!("{}\n", *Zeroizing::new(keypair.to_base58_string())).into_bytes()
+            }
+            ExportFormat::Hex => {
+                format!("{}\n", *Zeroizing::new(hex::encode(&bytes[..]))).into_bytes()
+            }
+            ExportFormat::Binary => bytes.to_vec(),
+            ExportFormat::Json | ExportFormat::Ndjson => unreachable!("not a per-key format"),
+        }))
+    }
+}
+
+/// Keypair of a result, re-derived from the master secret for hierarchical results.
+/// None for seed and program-derived addresses, which have no private key.
+pub fn result_keypair(
+    result: &VanityResult,
+    master_secret: Option<&[u8; 32]>,
+) -> Result<Option<Keypair>> {
+    let keypair = if !result.private_key.is_empty() {
+        let bytes = Zeroizing::new(
+            bs58::decode(result.private_key.expose())
+                .into_vec()
+                .map_err(|e| anyhow!("Invalid private key for {}: {}", result.public_key, e))?,
+        );
+        Keypair::from_bytes(&bytes)
+            .map_err(|e| anyhow!("Invalid private key for {}: {}", result.public_key, e))?
+    } else if let Some(index) = result.derivation_index {
+        let Some(master_secret) = master_secret else {
+            bail!(
+                "{} is a hierarchical result; pass --master-secret to re-derive it",
+                result.public_key
+            );
+        };
+        derive_hd_keypair(master_secret, index)
+    } else {
+        return Ok(None);
+    };
+
+    if keypair.pubkey().to_string() != result.public_key {
+        bail!(
+            "Private key does not belong to {}, wrong master secret?",
+            result.public_key
+        );
+    }
+    Ok(Some(keypair))
+}
+
+/// Writes the results to `destination`, a directory for per-key formats and a file otherwise.
+/// Every file is only readable by the current user. Returns how many results were written.
+pub fn export_results(
+    results: &[VanityResult],
+    format: ExportFormat,
+    destination: &str,
+    master_secret: Option<&[u8; 32]>,
+) -> Result<usize> {
+    match format {
+        ExportFormat::Json => {
+            let output = Zeroizing::new(serde_json::to_string_pretty(results)?);
+            write_private_file(destination, output.as_bytes())?;
+            Ok(results.len())
+        }
+        ExportFormat::Ndjson => {
+            let records = results
+                .iter()
+                .map(|result| Ok(Zeroizing::new(serde_json::to_string(result)?)))
+                .collect::<Result<Vec<_>>>()?;
+            // Sized up front so no reallocation leaves a copy of the secrets behind
+            let mut lines = Zeroizing::new(String::with_capacity(
+                records.iter().map(|record| record.len() + 1).sum(),
+            ));
+            for record in &records {
+                lines.push_str(record);
+                lines.push('\n');
+            }
+            write_private_file(destination, lines.as_bytes())?;
+            Ok(results.len())
+        }
+        _ => {
+            fs::create_dir_all(destination)?;
+            let extension = format.extension().unwrap();
+            let mut written = 0;
+            for result in results {
+                let Some(keypair) = result_keypair(result, master_secret)? else {
+                    continue;
+                };
+                let path =
+                    Path::new(destination).join(format!("{}.{}", result.public_key, extension));
+                write_private_file(&path.to_string_lossy(), &format.encode(&keypair)?)?;
+                written += 1;
+            }
+            Ok(written)
+        }
+    }
+}
+
+/// Splits the seed of every result into `<pubkey>.share-<i>-of-<n>` files in `destination`,
+/// to be handed to different custodians. Returns how many results were split.
+pub fn export_shares(
+    results: &[VanityResult],
+    scheme: ShamirScheme,
+    destination: &str,
+    master_secret: Option<&[u8; 32]>,
+) -> Result<usize> {
+    fs::create_dir_all(destination)?;
+    let mut written = 0;
+    for result in results {
+        let Some(keypair) = result_keypair(result, master_secret)? else {
+            continue;
+        };
+        for share in split(&keypair, scheme) {
+            let path = Path::new(destination).join(format!(
+                "{}.share-{}-of-{}",
+                result.public_key,
+                share.index(),
+                scheme.shares
+            ));
+            write_private_file(
+                &path.to_string_lossy(),
+                format!("{}\n", share.encode()).as_bytes(),
+            )?;
+        }
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Where a search with `--format` writes next to its `--output` file: the output's
+/// directory for per-key formats, like `solana-keygen grind`, or a sibling `.ndjson` file
+pub fn export_destination(output_path: &str, format: ExportFormat) -> String {
+    let output = Path::new(output_path);
+    match format {
+        ExportFormat::Json => output_path.to_string(),
+        ExportFormat::Ndjson => output
+            .with_extension("ndjson")
+            .to_string_lossy()
+            .into_owned(),
+        _ => match output.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_string_lossy().into_owned(),
+            _ => ".".to_string(),
+        },
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{bail, Result};
+use std::fmt;
+use std::ops::Range;
+
+use crate::pattern::{CharSet, Pattern};
+use crate::wordlist::WordList;
+
+/// Encoded lengths a `--length` filter can ask for
+const FILTER_LENGTHS: [usize; 2] = [43, 44];
+
+/// Checks applied only to addresses that already match the pattern
+pub struct Filters {
+    /// Patterns that must not match, e.g. confusable runs like `[1ij][1ij][1ij]`
+    excludes: Vec<Pattern>,
+    /// Words that must not appear anywhere, in any case
+    blocklist: Option<WordList>,
+    /// Exact encoded length
+    length: Option<usize>,
+    /// Characters allowed outside the matched region
+    outside_match: Option<CharSet>,
+    description: Vec<String>,
+}
+
+impl Filters {
+    pub fn new(
+        excludes: Vec<Pattern>,
+        blocklist: Option<WordList>,
+        length: Option<usize>,
+        allowed: Option<CharSet>,
+        forbidden: Option<CharSet>,
+    ) -> Result<Self> {
+        if let Some(length) = length {
+            if !FILTER_LENGTHS.contains(&length) {
+                bail!("Address length must be 43 or 44");
+            }
+        }
+
+        let mut description: Vec<String> = excludes
+            .iter()
+            .map(|exclude| format!("NOT ({})", exclude))
+            .collect();
+        if let Some(blocklist) = &blocklist {
+            description.push(format!("blocklist ({} words)", blocklist.len()));
+        }
+        if let Some(length) = length {
+            description.push(format!("length {}", length));
+        }
+        let outside_match = match (allowed, forbidden) {
+            (None, None) => None,
+            (allowed, forbidden) => {
+                let set = allowed
+                    .unwrap_or_else(CharSet::any)
+                    .difference(&forbidden.unwrap_or_default());
+                if set.fraction() == 0.0 {
+                    bail!("The allowed and forbidden characters leave nothing to use");
+                }
+                let limits: Vec<String> = [
+                    allowed.map(|allowed| format!("only {}", allowed)),
+                    forbidden.map(|forbidden| format!("no {}", forbidden)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                description.push(format!("{} outside the match", limits.join(" and ")));
+                Some(set)
+            }
+        };
+
+        Ok(Filters {
+            excludes,
+            blocklist,
+            length,
+            outside_match,
+            description,
+        })
+    }
+
+    pub fn excludes(&self) -> &[Pattern] {
+        &self.excludes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.description.is_empty()
+    }
+
+    /// Length, blocklist and character set checks on a matching address; the exclude
+    /// patterns are evaluated by the caller against the key
+    pub fn accepts(&self, address: &str, matched: &[Range<usize>]) -> bool {
+        if self.length.is_some_and(|length| address.len() != length) {
+            return false;
+        }
+        if self
+            .blocklist
+            .as_ref()
+            .is_some_and(|blocklist| blocklist.find(address).is_some())
+        {
+            return false;
+        }
+        self.outside_match.is_none_or(|set| {
+            address
+                .bytes()
+                .enumerate()
+                .filter(|(i, _)| !matched.iter().any(|range| range.contains(i)))
+                .all(|(_, c)| set.contains(c))
+        })
+    }
+
+    /// Chance that a random matching address passes, treating the filters as independent
+    /// of the match and of each other
+    pub fn probability(&self, matched_len: usize) -> f64 {
+        // Keys below 58^43 encode to 43 characters, the rest to 44
+        let short = 58f64.powi(43) / 2f64.powi(256);
+        let length = match self.length {
+            Some(43) => short,
+            Some(_) => 1.0 - short,
+            None => 1.0,
+        };
+        let excluded: f64 = self
+            .excludes
+            .iter()
+            .map(|exclude| 1.0 - exclude.probability())
+            .product();
+        let blocked = self
+            .blocklist
+            .as_ref()
+            .map_or(1.0, |blocklist| 1.0 - blocklist.probability());
+        let per_char = self.outside_match.map_or(1.0, |set| set.fraction());
+        let unmatched = self.length.unwrap_or(44).saturating_sub(matched_len);
+
+        length * excluded * blocked * per_char.powi(unmatched as i32)
+    }
+}
+
+impl fmt::Display for Filters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description.join(", "))
+    }
+}
@@ -0,0 +1,291 @@
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::pattern::BASE58_ALPHABET;
+
+/// Longest possible encoded address
+const MAX_ADDRESS_LEN: usize = 44;
+
+/// Edit operations allowed between the target and the address
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditMetric {
+    /// Substitutions, insertions and deletions
+    Levenshtein,
+    /// Substitutions only, so the matched text keeps the target's length
+    Hamming,
+}
+
+impl FromStr for EditMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "levenshtein" => Ok(EditMetric::Levenshtein),
+            "hamming" => Ok(EditMetric::Hamming),
+            _ => bail!("Edit metric must be levenshtein or hamming"),
+        }
+    }
+}
+
+/// Cost of each edit, parsed from `case=0.5,sub=1,indel=1` (missing entries cost 1)
+#[derive(Clone, Copy, Debug)]
+pub struct EditCosts {
+    /// Same letter in the other case, only charged when matching is case sensitive
+    pub case_flip: f64,
+    pub substitution: f64,
+    pub indel: f64,
+}
+
+impl Default for EditCosts {
+    fn default() -> Self {
+        EditCosts {
+            case_flip: 1.0,
+            substitution: 1.0,
+            indel: 1.0,
+        }
+    }
+}
+
+impl FromStr for EditCosts {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut costs = EditCosts::default();
+        for entry in s.split(',').map(str::trim) {
+            let Some((name, cost)) = entry.split_once('=') else {
+                bail!("Edit cost '{}' must look like name=cost", entry);
+            };
+            let cost: f64 = cost.parse()?;
+            if cost.is_nan() || cost <= 0.0 {
+                bail!("Edit cost '{}' must be positive", entry);
+            }
+            match name {
+                "case" => costs.case_flip = cost,
+                "sub" => costs.substitution = cost,
+                "indel" => costs.indel = cost,
+                _ => bail!("Unknown edit cost '{}' (use case, sub or indel)", name),
+            }
+        }
+        Ok(costs)
+    }
+}
+
+/// Text of the address that matched a fuzzy term and its edit distance to the target
+pub struct FuzzyMatch {
+    pub text: String,
+    pub distance: f64,
+    pub range: Range<usize>,
+}
+
+/// Literal prefix or anywhere term that may be up to `max_distance` edits away
+pub struct FuzzyTerm {
+    target: Vec<u8>,
+    anywhere: bool,
+    max_distance: f64,
+    metric: EditMetric,
+    costs: EditCosts,
+    case_sensitive: bool,
+}
+
+impl FuzzyTerm {
+    pub fn new(
+        target: &str,
+        anywhere: bool,
+        max_distance: f64,
+        metric: EditMetric,
+        costs: EditCosts,
+        case_sensitive: bool,
+    ) -> Self {
+        FuzzyTerm {
+            target: target.as_bytes().to_vec(),
+            anywhere,
+            max_distance,
+            metric,
+            costs,
+            case_sensitive,
+        }
+    }
+
+    fn cost(&self, expected: u8, actual: u8) -> f64 {
+        if expected == actual {
+            0.0
+        } else if expected.eq_ignore_ascii_case(&actual) {
+            if self.case_sensitive {
+                self.costs.case_flip
+            } else {
+                0.0
+            }
+        } else {
+            self.costs.substitution
+        }
+    }
+
+    pub fn target_len(&self) -> usize {
+        self.target.len()
+    }
+
+    /// Closest match within the budget, preferring the earliest on ties
+    pub fn find(&self, address: &str) -> Option<FuzzyMatch> {
+        let address = address.as_bytes();
+        let (start, end, distance) = match self.metric {
+            EditMetric::Hamming => self.find_hamming(address)?,
+            EditMetric::Levenshtein => self.find_levenshtein(address)?,
+        };
+        (distance <= self.max_distance).then(|| FuzzyMatch {
+            text: String::from_utf8_lossy(&address[start..end]).into_owned(),
+            distance,
+            range: start..end,
+        })
+    }
+
+    fn find_hamming(&self, address: &[u8]) -> Option<(usize, usize, f64)> {
+        let len = self.target.len();
+        let last_start = if self.anywhere {
+            address.len().checked_sub(len)?
+        } else {
+            0
+        };
+        (0..=last_start)
+            .filter_map(|start| {
+                let window = address.get(start..start + len)?;
+                let distance = self
+                    .target
+                    .iter()
+                    .zip(window)
+                    .map(|(&expected, &actual)| self.cost(expected, actual))
+                    .sum::<f64>();
+                Some((start, start + len, distance))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+    }
+
+    /// Weighted edit distance by columns over the address. A prefix must start at 0,
+    /// anywhere matches may start at any column; each cell carries its match start.
+    fn find_levenshtein(&self, address: &[u8]) -> Option<(usize, usize, f64)> {
+        let indel = self.costs.indel;
+        let mut column: Vec<(f64, usize)> = (0..=self.target.len())
+            .map(|i| (i as f64 * indel, 0))
+            .collect();
+        let mut best = (0, 0, column[self.target.len()].0);
+
+        for (j, &actual) in address.iter().enumerate() {
+            let mut next = Vec::with_capacity(column.len());
+            next.push(if self.anywhere {
+                (0.0, j + 1)
+            } else {
+                ((j + 1) as f64 * indel, 0)
+            });
+            for (i, &expected) in self.target.iter().enumerate() {
+                let substitute = (column[i].0 + self.cost(expected, actual), column[i].1);
+                let insert = (column[i + 1].0 + indel, column[i + 1].1);
+                let delete = (next[i].0 + indel, next[i].1);
+                next.push(
+                    [substitute, insert, delete]
+                        .into_iter()
+                        .min_by(|a, b| a.0.total_cmp(&b.0))
+                        .unwrap(),
+                );
+            }
+            column = next;
+
+            let (distance, start) = column[self.target.len()];
+            if distance < best.2 {
+                best = (start, j + 1, distance);
+            }
+            // A prefix cannot get closer once every cell is over budget
+            if !self.anywhere && column.iter().all(|(cost, _)| *cost > self.max_distance) {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    /// Chance that a random address has a match within the budget, by a union bound
+    /// over where the insertions and deletions go
+    pub fn probability(&self) -> f64 {
+        let len = self.target.len();
+        let max_indels = match self.metric {
+            EditMetric::Hamming => 0,
+            EditMetric::Levenshtein => (self.max_distance / self.costs.indel) as usize,
+        };
+
+        let mut p = 0.0;
+        for deletions in 0..=max_indels.min(len) {
+            for insertions in 0..=max_indels - deletions {
+                let budget = self.max_distance - (deletions + insertions) as f64 * self.costs.indel;
+                let placements =
+                    binomial(len, deletions) * binomial(len - deletions + insertions, insertions);
+                p += placements * self.substitution_probability(len - deletions, budget);
+            }
+        }
+        let p = p.min(1.0);
+
+        if self.anywhere {
+            let positions = (MAX_ADDRESS_LEN + 1).saturating_sub(len);
+            1.0 - (1.0 - p).powi(positions as i32)
+        } else {
+            p
+        }
+    }
+
+    /// Chance that the first `kept` target characters are matched with substitutions
+    /// costing at most `budget`
+    fn substitution_probability(&self, kept: usize, budget: f64) -> f64 {
+        let alphabet = BASE58_ALPHABET.len() as f64;
+        let flip_cost = if self.case_sensitive {
+            self.costs.case_flip
+        } else {
+            0.0
+        };
+
+        // Distribution of the total cost, keyed by micro-units so equal costs merge
+        let mut costs: BTreeMap<u64, f64> = BTreeMap::from([(0, 1.0)]);
+        for &c in self.target.iter().take(kept) {
+            let flipped = if c.is_ascii_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            };
+            // Only variants inside the alphabet can occur, e.g. 'l' matches just 'L'
+            let mut outcomes = Vec::new();
+            if BASE58_ALPHABET.contains(&c) {
+                outcomes.push((0.0, 1.0 / alphabet));
+            }
+            if flipped != c && BASE58_ALPHABET.contains(&flipped) {
+                outcomes.push((flip_cost, 1.0 / alphabet));
+            }
+            let others = alphabet - outcomes.len() as f64;
+            outcomes.push((self.costs.substitution, others / alphabet));
+
+            let mut next = BTreeMap::new();
+            for (&total, &p) in &costs {
+                for &(cost, q) in &outcomes {
+                    let total = total + (cost * 1e6).round() as u64;
+                    if total as f64 / 1e6 <= budget + 1e-9 {
+                        *next.entry(total).or_insert(0.0) += p * q;
+                    }
+                }
+            }
+            costs = next;
+        }
+        costs.values().sum()
+    }
+}
+
+impl fmt::Display for FuzzyTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let target = String::from_utf8_lossy(&self.target);
+        if self.anywhere {
+            write!(f, "{}~{}", target, self.max_distance)
+        } else {
+            write!(f, "^{}~{}", target, self.max_distance)
+        }
+    }
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
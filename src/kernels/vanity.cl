@@ -178,4 +178,248 @@ __kernel void generate_random_seeds_vectorized(
     seeds.w = generate_seeds_batch(base_seed.w, work_item_id, 3).x;
     
     output_seeds[gid] = seeds;
-} 
\ No newline at end of file
+} 
+// Initial SHA256 state, shared by the HMAC helpers below
+constant uint sha256_iv[8] = {
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+};
+
+uint bswap32(uint x) {
+    return (x >> 24) | ((x >> 8) & 0xff00) | ((x << 8) & 0xff0000) | (x << 24);
+}
+
+// HMAC-SHA256 keyed with a 32-byte master secret over an 8-byte little-endian index.
+// Must stay bit-identical to utils::derive_hd_seed on the host.
+void hmac_sha256_index(__constant const uint* key, ulong index, uint out[8]) {
+    __private uint w[64];
+    uint inner[8];
+    uint outer[8];
+
+    // Inner hash: (key ^ ipad) || index
+    for (int i = 0; i < 8; i++) {
+        inner[i] = sha256_iv[i];
+        outer[i] = sha256_iv[i];
+    }
+    for (int i = 0; i < 16; i++) {
+        w[i] = (i < 8 ? key[i] : 0) ^ 0x36363636;
+    }
+    sha256_transform_optimized(inner, w);
+
+    for (int i = 0; i < 16; i++) {
+        w[i] = 0;
+    }
+    w[0] = bswap32((uint)index);
+    w[1] = bswap32((uint)(index >> 32));
+    w[2] = 0x80000000;
+    w[15] = (64 + 8) * 8;
+    sha256_transform_optimized(inner, w);
+
+    // Outer hash: (key ^ opad) || inner
+    for (int i = 0; i < 16; i++) {
+        w[i] = (i < 8 ? key[i] : 0) ^ 0x5c5c5c5c;
+    }
+    sha256_transform_optimized(outer, w);
+
+    for (int i = 0; i < 16; i++) {
+        w[i] = 0;
+    }
+    for (int i = 0; i < 8; i++) {
+        w[i] = inner[i];
+    }
+    w[8] = 0x80000000;
+    w[15] = (64 + 32) * 8;
+    sha256_transform_optimized(outer, w);
+
+    for (int i = 0; i < 8; i++) {
+        out[i] = outer[i];
+    }
+}
+
+// Derive hierarchical seeds for indices [start_index, start_index + total_seeds_needed).
+// master_key holds the 32-byte secret as 8 big-endian words; each output seed is
+// 8 big-endian words.
+__kernel void derive_hd_seeds(
+    __constant const uint* master_key,
+    ulong start_index,
+    __global uint* output_seeds,
+    uint total_seeds_needed
+) {
+    uint gid = get_global_id(0);
+    if (gid >= total_seeds_needed) {
+        return;
+    }
+
+    uint seed[8];
+    hmac_sha256_index(master_key, start_index + gid, seed);
+
+    for (int i = 0; i < 8; i++) {
+        output_seeds[gid * 8 + i] = seed[i];
+    }
+}
+
+// Base58 alphabet used by Solana addresses
+constant uchar base58_alphabet[58] = {
+    '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G',
+    'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y',
+    'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'm', 'n', 'o', 'p',
+    'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z'
+};
+
+// Alphabet for generated create_with_seed seeds, must match seed_address::SEED_ALPHABET
+constant uchar seed_alphabet[62] = {
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F',
+    'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V',
+    'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l',
+    'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z'
+};
+
+// Encode 32 bytes as base58, returning the string length (at most 44)
+int base58_encode32(const uchar bytes[32], uchar out[44]) {
+    uchar digits[44];
+    int digits_len = 0;
+
+    for (int i = 0; i < 32; i++) {
+        uint carry = bytes[i];
+        for (int j = 0; j < digits_len; j++) {
+            carry += (uint)digits[j] << 8;
+            digits[j] = carry % 58;
+            carry /= 58;
+        }
+        while (carry > 0) {
+            digits[digits_len++] = carry % 58;
+            carry /= 58;
+        }
+    }
+
+    int len = 0;
+    for (int i = 0; i < 32 && bytes[i] == 0; i++) {
+        out[len++] = '1';
+    }
+    for (int i = digits_len - 1; i >= 0; i--) {
+        out[len++] = base58_alphabet[digits[i]];
+    }
+    return len;
+}
+
+uchar ascii_lower(uchar c) {
+    return (c >= 'A' && c <= 'Z') ? c + 32 : c;
+}
+
+bool chars_match(uchar a, uchar b, uint case_sensitive) {
+    return case_sensitive ? a == b : ascii_lower(a) == ascii_lower(b);
+}
+
+bool address_matches(
+    const uchar* address,
+    int address_len,
+    __global const uchar* starts_with,
+    uint starts_with_len,
+    __global const uchar* ends_with,
+    uint ends_with_len,
+    uint case_sensitive
+) {
+    if (starts_with_len > address_len || ends_with_len > address_len) {
+        return false;
+    }
+    for (uint i = 0; i < starts_with_len; i++) {
+        if (!chars_match(address[i], starts_with[i], case_sensitive)) {
+            return false;
+        }
+    }
+    for (uint i = 0; i < ends_with_len; i++) {
+        if (!chars_match(address[address_len - ends_with_len + i], ends_with[i], case_sensitive)) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// Grind Pubkey::create_with_seed(base, seed, owner) = sha256(base || seed || owner).
+// Each seed is the 8-character run prefix followed by the work item's index in base62,
+// matching seed_address::seed_for_index. Matching indexes are appended to hits.
+__kernel void grind_seed_addresses(
+    __global const uchar* base,
+    __global const uchar* owner,
+    __global const uchar* run_prefix,
+    ulong start_index,
+    uint total_seeds_needed,
+    __global const uchar* starts_with,
+    uint starts_with_len,
+    __global const uchar* ends_with,
+    uint ends_with_len,
+    uint case_sensitive,
+    __global const uchar* byte_value,
+    __global const uchar* byte_mask,
+    __global ulong* hits,
+    __global uint* hit_count,
+    uint max_hits
+) {
+    uint gid = get_global_id(0);
+    if (gid >= total_seeds_needed) {
+        return;
+    }
+
+    // base (32) || seed (16) || owner (32) = 80 bytes
+    uchar message[80];
+    for (int i = 0; i < 32; i++) {
+        message[i] = base[i];
+        message[48 + i] = owner[i];
+    }
+    for (int i = 0; i < 8; i++) {
+        message[32 + i] = run_prefix[i];
+    }
+    ulong index = start_index + gid;
+    for (int i = 7; i >= 0; i--) {
+        message[40 + i] = seed_alphabet[index % 62];
+        index /= 62;
+    }
+
+    uint state[8];
+    for (int i = 0; i < 8; i++) {
+        state[i] = sha256_iv[i];
+    }
+
+    __private uint w[64];
+    for (int i = 0; i < 16; i++) {
+        w[i] = ((uint)message[i * 4] << 24) | ((uint)message[i * 4 + 1] << 16) |
+               ((uint)message[i * 4 + 2] << 8) | (uint)message[i * 4 + 3];
+    }
+    sha256_transform_optimized(state, w);
+
+    for (int i = 0; i < 16; i++) {
+        w[i] = 0;
+    }
+    for (int i = 0; i < 4; i++) {
+        w[i] = ((uint)message[64 + i * 4] << 24) | ((uint)message[64 + i * 4 + 1] << 16) |
+               ((uint)message[64 + i * 4 + 2] << 8) | (uint)message[64 + i * 4 + 3];
+    }
+    w[4] = 0x80000000;
+    w[15] = 80 * 8;
+    sha256_transform_optimized(state, w);
+
+    uchar hash[32];
+    for (int i = 0; i < 8; i++) {
+        hash[i * 4] = state[i] >> 24;
+        hash[i * 4 + 1] = state[i] >> 16;
+        hash[i * 4 + 2] = state[i] >> 8;
+        hash[i * 4 + 3] = state[i];
+    }
+
+    // Raw byte constraint first, it is much cheaper than base58 encoding
+    for (int i = 0; i < 32; i++) {
+        if ((hash[i] & byte_mask[i]) != byte_value[i]) {
+            return;
+        }
+    }
+
+    uchar address[44];
+    int address_len = base58_encode32(hash, address);
+    if (address_matches(address, address_len, starts_with, starts_with_len, ends_with,
+                        ends_with_len, case_sensitive)) {
+        uint slot = atomic_inc(hit_count);
+        if (slot < max_hits) {
+            hits[slot] = start_index + gid;
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::{anyhow, bail, Result};
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::secret::Secret;
+use crate::utils::{load_results_file, read_secret_file, VanityResult};
+
+/// scrypt cost of new keystores: 2^17 iterations and 128 MiB, about a second per unlock
+pub const DEFAULT_LOG_N: u8 = 17;
+
+/// Associated data of the passphrase check value
+const CHECK_AAD: &[u8] = b"vanity keystore";
+
+/// Readable part of an encrypted results file: how to derive the key from the passphrase
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KeystoreHeader {
+    pub kdf: String,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    /// Hex salt of the KDF
+    pub salt: String,
+    pub cipher: String,
+    /// Hex nonce and tag of an empty message, to reject a wrong passphrase up front
+    pub check: String,
+}
+
+/// Private key, mnemonic and BIP39 passphrase of one result, sealed to its public key
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SealedSecret {
+    /// Hex of the 24-byte XChaCha20 nonce
+    pub nonce: String,
+    /// Hex of the encrypted JSON secret fields and their tag
+    pub ciphertext: String,
+}
+
+/// Plaintext secrets of one result while they are encrypted or decrypted
+#[derive(Serialize, Deserialize)]
+pub struct SecretFields {
+    private_key: Secret,
+    mnemonic: Option<Secret>,
+    bip39_passphrase: Option<Secret>,
+}
+
+impl SecretFields {
+    /// Moves the secrets out of a result, None if it has none
+    pub fn take(result: &mut VanityResult) -> Option<Self> {
+        if result.private_key.is_empty() && result.mnemonic.is_none() {
+            return None;
+        }
+        Some(SecretFields {
+            private_key: std::mem::take(&mut result.private_key),
+            mnemonic: result.mnemonic.take(),
+            bip39_passphrase: result.bip39_passphrase.take(),
+        })
+    }
+
+    /// Moves the secrets back into a result
+    pub fn restore(self, result: &mut VanityResult) {
+        result.private_key = self.private_key;
+        result.mnemonic = self.mnemonic;
+        result.bip39_passphrase = self.bip39_passphrase;
+    }
+
+    pub fn to_json(&self) -> Result<Zeroizing<Vec<u8>>> {
+        Ok(Zeroizing::new(serde_json::to_vec(self)?))
+    }
+
+    pub fn from_json(plaintext: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(plaintext)?)
+    }
+}
+
+/// Passphrase-derived key that seals and opens the secrets of a results file
+pub struct Keystore {
+    header: KeystoreHeader,
+    cipher: XChaCha20Poly1305,
+}
+
+impl Keystore {
+    /// New keystore with a random salt
+    pub fn create(passphrase: &str, log_n: u8) -> Result<Self> {
+        let salt: [u8; 16] = rand::thread_rng().gen();
+        let mut header = KeystoreHeader {
+            kdf: "scrypt".to_string(),
+            log_n,
+            r: 8,
+            p: 1,
+            salt: hex::encode(salt),
+            cipher: "xchacha20poly1305".to_string(),
+            check: String::new(),
+        };
+        let cipher = derive_cipher(&header, passphrase)?;
+        let nonce: [u8; 24] = rand::thread_rng().gen();
+        let tag = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &[],
+                    aad: CHECK_AAD,
+                },
+            )
+            .map_err(|_| anyhow!("Encryption failed"))?;
+        header.check = hex::encode([&nonce[..], &tag].concat());
+        Ok(Keystore { header, cipher })
+    }
+
+    /// Derives the key of an existing keystore, failing on a wrong passphrase
+    pub fn open(header: &KeystoreHeader, passphrase: &str) -> Result<Self> {
+        if header.kdf != "scrypt" || header.cipher != "xchacha20poly1305" {
+            bail!("Unsupported keystore {} with {}", header.kdf, header.cipher);
+        }
+        let cipher = derive_cipher(header, passphrase)?;
+        let check = hex::decode(&header.check)?;
+        if check.len() < 24 {
+            bail!("Corrupt keystore check value");
+        }
+        let (nonce, tag) = check.split_at(24);
+        cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: tag,
+                    aad: CHECK_AAD,
+                },
+            )
+            .map_err(|_| anyhow!("Wrong keystore passphrase"))?;
+        Ok(Keystore {
+            header: header.clone(),
+            cipher,
+        })
+    }
+
+    pub fn header(&self) -> &KeystoreHeader {
+        &self.header
+    }
+
+    /// Moves the secrets of a result into its sealed field; sealed or secretless results are kept
+    pub fn seal(&self, result: &mut VanityResult) -> Result<()> {
+        let Some(fields) = SecretFields::take(result) else {
+            return Ok(());
+        };
+        let nonce: [u8; 24] = rand::thread_rng().gen();
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &fields.to_json()?,
+                    aad: result.public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Encryption failed"))?;
+        result.sealed_secret = Some(SealedSecret {
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        });
+        Ok(())
+    }
+
+    /// Restores the secrets of a sealed result
+    pub fn unseal(&self, result: &mut VanityResult) -> Result<()> {
+        let Some(sealed) = result.sealed_secret.take() else {
+            return Ok(());
+        };
+        let nonce = hex::decode(&sealed.nonce)?;
+        if nonce.len() != 24 {
+            bail!("Corrupt nonce for {}", result.public_key);
+        }
+        let plaintext = Zeroizing::new(
+            self.cipher
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: &hex::decode(&sealed.ciphertext)?,
+                        aad: result.public_key.as_bytes(),
+                    },
+                )
+                .map_err(|_| anyhow!("Secret of {} fails authentication", result.public_key))?,
+        );
+        SecretFields::from_json(&plaintext)?.restore(result);
+        Ok(())
+    }
+}
+
+fn derive_cipher(header: &KeystoreHeader, passphrase: &str) -> Result<XChaCha20Poly1305> {
+    let params = scrypt::Params::new(header.log_n, header.r, header.p, 32)
+        .map_err(|e| anyhow!("Invalid scrypt parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(
+        passphrase.as_bytes(),
+        &hex::decode(&header.salt)?,
+        &params,
+        &mut *key,
+    )
+    .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&*key)))
+}
+
+/// Reads the keystore passphrase from a file descriptor, or prompts for it on the terminal
+/// (twice when `confirm` is set, for a new keystore)
+pub fn read_passphrase(fd: Option<i32>, confirm: bool) -> Result<Secret> {
+    let passphrase = match fd {
+        Some(fd) => read_secret_file(&format!("/dev/fd/{}", fd))?,
+        None => {
+            let passphrase = Secret::new(rpassword::prompt_password("Keystore passphrase: ")?);
+            if confirm
+                && Secret::new(rpassword::prompt_password("Repeat passphrase: ")?) != passphrase
+            {
+                bail!("Passphrases do not match");
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        bail!("Keystore passphrase must not be empty");
+    }
+    Ok(passphrase)
+}
+
+/// Opens the keystore of an encrypted results file, or creates one for a new or plain file
+pub fn open_or_create(path: &str, passphrase_fd: Option<i32>) -> Result<Keystore> {
+    match load_results_file(path)?.1 {
+        Some(header) => Keystore::open(&header, read_passphrase(passphrase_fd, false)?.expose()),
+        None => Keystore::create(
+            read_passphrase(passphrase_fd, true)?.expose(),
+            DEFAULT_LOG_N,
+        ),
+    }
+}
+
+/// Results of a plain or encrypted file, limited to the `selected` public keys unless empty,
+/// with only those secrets decrypted
+pub fn unlock_results(
+    path: &str,
+    selected: &[String],
+    passphrase_fd: Option<i32>,
+) -> Result<Vec<VanityResult>> {
+    let (results, header) = load_results_file(path)?;
+    if let Some(missing) = selected
+        .iter()
+        .find(|pubkey| !results.iter().any(|result| &result.public_key == *pubkey))
+    {
+        bail!("{} is not in {}", missing, path);
+    }
+    let mut results: Vec<VanityResult> = results
+        .into_iter()
+        .filter(|result| selected.is_empty() || selected.contains(&result.public_key))
+        .collect();
+
+    unseal_results(&mut results, header.as_ref(), passphrase_fd)?;
+    Ok(results)
+}
+
+/// Decrypts the sealed secrets of results read from a keystore, asking for the passphrase
+/// only if there are any
+pub fn unseal_results(
+    results: &mut [VanityResult],
+    header: Option<&KeystoreHeader>,
+    passphrase_fd: Option<i32>,
+) -> Result<()> {
+    if let Some(header) = header {
+        if results.iter().any(|result| result.sealed_secret.is_some()) {
+            let keystore = Keystore::open(header, read_passphrase(passphrase_fd, false)?.expose())?;
+            for result in results {
+                keystore.unseal(result)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,14 +1,63 @@
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use rand::SeedableRng;
-use solana_sdk::signature::Signer;
+use anyhow::{anyhow, bail, Result};
+use clap::{Args, Parser, Subcommand};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signer::keypair::generate_seed_from_seed_phrase_and_passphrase;
+use std::io::Read;
+use std::str::FromStr;
+use utils::{
+    add_result, check_pattern_match, derive_hd_keypair, derive_hd_seed, display_results,
+    load_master_secret, load_or_create_master_secret, load_results_file, read_mnemonic,
+    read_results_file, read_secret_file, reveal, save_encrypted_results, save_results, Provenance,
+    ResultsLock, SeedDerivation, VanityResult, WalletSeed, RESULTS_SCHEMA_VERSION,
+};
 use vanity::VanityGenerator;
+use zeroize::Zeroizing;
 
+mod associated_token;
+mod bytes;
+mod chain;
+mod export;
+mod filter;
+mod fuzzy;
+mod keystore;
 mod opencl;
+mod pattern;
+mod pda;
+mod recipient;
+mod score;
+mod secret;
+mod seed_address;
+mod shamir;
+mod sign;
+mod store;
 mod utils;
 mod vanity;
+mod verify;
+mod wordlist;
 
+use associated_token::{AssociatedToken, MatchTarget, TokenProgram};
+use bytes::{ByteMask, KeyRange};
+use chain::Chain;
+use export::{export_results, export_shares, ExportFormat};
+use filter::Filters;
+use fuzzy::{EditCosts, EditMetric};
+use keystore::{open_or_create, unlock_results, unseal_results, Keystore};
 use opencl::OpenCLManager;
+use pattern::{CharSet, Pattern};
+use pda::{PdaGenerator, SeedComponent, VariableSeed};
+use recipient::AgeRecipients;
+use score::{Budget, CompositeScorer, ScoreConfig};
+use secret::Secret;
+use seed_address::SeedAddressGenerator;
+use shamir::{combine, split, ShamirScheme, Share};
+use sign::{
+    decode_transaction, encode_transaction, load_signer, read_message, sign_message,
+    sign_transaction, verify_message, verify_transaction,
+};
+use store::ResultFilter;
+use verify::verify_results;
+use wordlist::{WordCase, WordList, WordPosition};
 
 #[derive(Parser)]
 #[command(name = "solana-vanity")]
@@ -17,8 +66,256 @@ use opencl::OpenCLManager;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Print private keys, mnemonics and BIP39 passphrases instead of redacting them
+    #[arg(long, global = true)]
+    show_secrets: bool,
+}
+
+/// Dictionary options shared by the search commands
+#[derive(Args)]
+struct WordlistArgs {
+    /// File with one word per line; the address must contain one of them
+    #[arg(long)]
+    wordlist: Option<String>,
+
+    /// Ignore words shorter than this
+    #[arg(long, default_value = "5", requires = "wordlist")]
+    min_word_length: usize,
+
+    /// Where the word has to appear: prefix, suffix or anywhere
+    #[arg(long, default_value = "prefix", requires = "wordlist")]
+    word_position: WordPosition,
+
+    /// Case of the word in the address: any, exact or capitalized
+    #[arg(long, default_value = "any", requires = "wordlist")]
+    word_case: WordCase,
+}
+
+impl WordlistArgs {
+    fn apply(self, pattern: Pattern) -> Result<Pattern> {
+        Ok(match self.wordlist {
+            Some(path) => pattern.with_wordlist(WordList::load(
+                &path,
+                self.min_word_length,
+                self.word_position,
+                self.word_case,
+            )?),
+            None => pattern,
+        })
+    }
+}
+
+/// Approximate matching options shared by the search commands
+#[derive(Args)]
+struct FuzzyArgs {
+    /// Let literal prefix and anywhere terms match within this edit distance
+    #[arg(long)]
+    max_distance: Option<f64>,
+
+    /// Edits counted by --max-distance: levenshtein or hamming
+    #[arg(long, default_value = "levenshtein", requires = "max_distance")]
+    edit_metric: EditMetric,
+
+    /// Cost of each edit, e.g. "case=0.5,sub=1,indel=1"; case flips only count with -C
+    #[arg(long, requires = "max_distance")]
+    edit_costs: Option<EditCosts>,
+}
+
+impl FuzzyArgs {
+    fn apply(self, pattern: Pattern) -> Result<Pattern> {
+        match self.max_distance {
+            Some(max_distance) => pattern.with_max_distance(
+                max_distance,
+                self.edit_metric,
+                self.edit_costs.unwrap_or_default(),
+            ),
+            None => Ok(pattern),
+        }
+    }
+}
+
+/// Structural filters shared by the search commands, checked after the pattern matches
+#[derive(Args)]
+struct FilterArgs {
+    /// Reject addresses that also match this pattern expression, e.g. "[1ij][1ij][1ij]" (repeatable)
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// File with one word per line that must not appear anywhere in the address, in any case
+    #[arg(long)]
+    blocklist: Option<String>,
+
+    /// Require this encoded address length: 43 or 44
+    #[arg(long)]
+    length: Option<usize>,
+
+    /// Characters allowed outside the matched part, as a class like "a-z1-9"
+    #[arg(long)]
+    allowed_chars: Option<CharSet>,
+
+    /// Characters forbidden outside the matched part, as a class like "1iLj"
+    #[arg(long)]
+    forbidden_chars: Option<CharSet>,
+}
+
+impl FilterArgs {
+    fn apply(self, pattern: Pattern) -> Result<Pattern> {
+        let excludes = self
+            .exclude
+            .iter()
+            .map(|expr| Pattern::parse_for(expr, pattern.case_sensitive(), pattern.chain()))
+            .collect::<Result<_>>()?;
+        let blocklist = self
+            .blocklist
+            .map(|path| WordList::load(&path, 1, WordPosition::Anywhere, WordCase::Any))
+            .transpose()?;
+        Ok(pattern.with_filters(Filters::new(
+            excludes,
+            blocklist,
+            self.length,
+            self.allowed_chars,
+            self.forbidden_chars,
+        )?))
+    }
+}
+
+/// Raw key byte options shared by the search commands
+#[derive(Args)]
+struct BytesArgs {
+    /// Hex the raw public key bytes must start with, e.g. "00" or "a" for the first nibble
+    #[arg(long)]
+    bytes_prefix: Option<String>,
+
+    /// Hex mask of the bits --bytes-prefix constrains, e.g. "f0" for only the first nibble
+    #[arg(long, requires = "bytes_prefix")]
+    bytes_mask: Option<String>,
+
+    /// Require the raw key to sort before this pubkey
+    #[arg(long)]
+    less_than: Option<Pubkey>,
+
+    /// Require the raw key to sort after this pubkey
+    #[arg(long)]
+    greater_than: Option<Pubkey>,
+
+    /// Require the raw key in START..END, pubkeys or zero-padded hex prefixes; either side may be empty
+    #[arg(long)]
+    key_range: Option<String>,
 }
 
+impl BytesArgs {
+    fn apply(self, pattern: Pattern) -> Result<Pattern> {
+        let pattern = match self.bytes_prefix {
+            Some(prefix) => {
+                pattern.with_bytes(ByteMask::parse(&prefix, self.bytes_mask.as_deref())?)
+            }
+            None => pattern,
+        };
+
+        let ranges = [
+            self.less_than
+                .map(|bound| Ok(KeyRange::less_than(&bound.to_bytes()))),
+            self.greater_than
+                .map(|bound| Ok(KeyRange::greater_than(&bound.to_bytes()))),
+            self.key_range.as_deref().map(KeyRange::parse),
+        ];
+        let mut range = None;
+        for bound in ranges.into_iter().flatten() {
+            range = Some(bound?.intersect(&range.unwrap_or_default()));
+        }
+        Ok(match range {
+            Some(range) if range.is_empty() => bail!("The key range constraints leave no keys"),
+            Some(range) => pattern.with_key_range(range),
+            None => pattern,
+        })
+    }
+}
+
+/// Word list, distance, raw byte and filter options added to the pattern options
+#[derive(Args)]
+struct MatchArgs {
+    #[command(flatten)]
+    wordlist: WordlistArgs,
+
+    #[command(flatten)]
+    fuzzy: FuzzyArgs,
+
+    #[command(flatten)]
+    bytes: BytesArgs,
+
+    #[command(flatten)]
+    filters: FilterArgs,
+}
+
+/// Which saved results `results list` and `results search` print
+#[derive(Args)]
+struct QueryArgs {
+    /// Only results of this exact pattern spec
+    #[arg(long)]
+    pattern: Option<String>,
+
+    /// Only results of this run ID
+    #[arg(long)]
+    run: Option<String>,
+
+    /// Only results with this label
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Only results found at or after this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long)]
+    since: Option<String>,
+
+    /// Only results found before this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long)]
+    until: Option<String>,
+
+    /// Only the newest N results
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+impl QueryArgs {
+    fn filter(self, text: Option<String>) -> Result<ResultFilter> {
+        Ok(ResultFilter {
+            pattern: self.pattern,
+            run_id: self.run,
+            label: self.label,
+            since: self.since.as_deref().map(parse_date).transpose()?,
+            until: self.until.as_deref().map(parse_date).transpose()?,
+            text,
+            limit: self.limit,
+        })
+    }
+}
+
+/// A day at midnight UTC, or an RFC 3339 timestamp
+fn parse_date(text: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    chrono::DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&chrono::Utc))
+        .map_err(|_| anyhow!("Invalid date '{}', use YYYY-MM-DD or RFC 3339", text))
+}
+
+/// Builds the search pattern from the pattern options and the extra matching options
+fn build_pattern(
+    pattern: Option<String>,
+    starts_with: Option<String>,
+    ends_with: Option<String>,
+    case_sensitive: bool,
+    chain: Chain,
+    matching: MatchArgs,
+) -> Result<Pattern> {
+    let pattern = Pattern::from_args(pattern, starts_with, ends_with, case_sensitive, chain)?;
+    let pattern = matching.wordlist.apply(matching.fuzzy.apply(pattern)?)?;
+    matching.filters.apply(matching.bytes.apply(pattern)?)
+}
+
+// Parsed once, so the size of the search variants does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Search for vanity public keys
@@ -31,6 +328,25 @@ enum Commands {
         #[arg(long, short)]
         ends_with: Option<String>,
 
+        /// Pattern expression such as "^S[oO]l AND (x$ OR @5:??z)", instead of --starts-with/--ends-with
+        #[arg(long, short, conflicts_with_all = ["starts_with", "ends_with"])]
+        pattern: Option<String>,
+
+        /// Chain whose address format the patterns match: solana, aptos, sui, near or stellar.
+        /// Wordlists, distances, filters and best-effort modes assume base58 and need solana.
+        #[arg(
+            long,
+            default_value = "solana",
+            conflicts_with_all = [
+                "wordlist", "max_distance", "blocklist", "length", "allowed_chars",
+                "forbidden_chars", "ata_mint", "use_mnemonic", "best_effort"
+            ]
+        )]
+        chain: Chain,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+
         /// Number of vanity addresses to generate
         #[arg(long, short, default_value = "1")]
         count: usize,
@@ -50,6 +366,421 @@ enum Commands {
         /// Output file to save results
         #[arg(long, short, default_value = "vanity_results.json")]
         output: String,
+
+        /// Also write each found key as keypair, base58, hex or binary files in the output's
+        /// directory, or as an ndjson stream next to it
+        #[arg(long, default_value = "json")]
+        format: ExportFormat,
+
+        /// Encrypt private keys and mnemonics in the output file with a passphrase
+        #[arg(long, conflicts_with = "format")]
+        encrypt: bool,
+
+        /// Read the keystore passphrase from this file descriptor instead of prompting
+        #[arg(long)]
+        keystore_passphrase_fd: Option<i32>,
+
+        /// Encrypt the secrets of each hit to an age public key (age1...) or recipients file
+        /// as soon as it is found, so only ciphertext is written or printed
+        #[arg(long, conflicts_with_all = ["encrypt", "format", "master_secret", "best_effort"])]
+        recipient: Option<String>,
+
+        /// Derive every candidate from this master secret file (created if missing)
+        /// and store only the index of each hit
+        #[arg(long)]
+        master_secret: Option<String>,
+
+        /// First index to try in hierarchical mode
+        #[arg(long, default_value = "0", requires = "master_secret")]
+        start_index: u64,
+
+        /// Grind BIP39 mnemonics and derive the key at m/44'/501'/0'/0' for wallet import
+        #[arg(long, conflicts_with = "master_secret")]
+        use_mnemonic: bool,
+
+        /// Number of words in generated mnemonics
+        #[arg(long, default_value = "12", value_parser = ["12", "24"], requires = "use_mnemonic")]
+        word_count: String,
+
+        /// File containing the optional BIP39 passphrase
+        #[arg(long, requires = "use_mnemonic")]
+        passphrase_file: Option<String>,
+
+        /// Derive the associated token account of each owner for this mint
+        #[arg(long)]
+        ata_mint: Option<Pubkey>,
+
+        /// Token program of the mint: spl-token, token-2022 or a program ID
+        #[arg(long, default_value = "spl-token", requires = "ata_mint")]
+        token_program: TokenProgram,
+
+        /// Address the patterns apply to: ata, owner or both
+        #[arg(long, default_value = "ata", requires = "ata_mint")]
+        match_target: MatchTarget,
+
+        /// Keep the best-scoring addresses instead of exact matches, scored by a weighted
+        /// list of prefix, case, runs and words:<file>, e.g. "prefix,case=0.5,runs=0.25"
+        #[arg(long, group = "best_effort")]
+        score: Option<String>,
+
+        /// Find ever longer prefixes of this target, saving each new length as it is reached
+        #[arg(
+            long,
+            group = "best_effort",
+            conflicts_with_all = ["pattern", "starts_with", "ends_with"]
+        )]
+        progressive: Option<String>,
+
+        /// Target string for the prefix and case score components
+        #[arg(long, requires = "score")]
+        target: Option<String>,
+
+        /// Number of best addresses to keep in score mode
+        #[arg(long, default_value = "10", requires = "score")]
+        top: usize,
+
+        /// Stop score or progressive mode after this many seconds
+        #[arg(long, requires = "best_effort")]
+        max_time: Option<u64>,
+
+        /// Stop score or progressive mode after this many attempts
+        #[arg(long, requires = "best_effort")]
+        max_attempts: Option<u64>,
+    },
+
+    /// Search the derivation paths of an existing wallet seed phrase for a vanity address
+    SearchDerivationPath {
+        /// Pattern that the public key should start with
+        #[arg(long, short)]
+        starts_with: Option<String>,
+
+        /// Pattern that the public key should end with
+        #[arg(long, short)]
+        ends_with: Option<String>,
+
+        /// Pattern expression such as "^S[oO]l AND (x$ OR @5:??z)", instead of --starts-with/--ends-with
+        #[arg(long, short, conflicts_with_all = ["starts_with", "ends_with"])]
+        pattern: Option<String>,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+
+        /// Number of vanity addresses to generate
+        #[arg(long, short, default_value = "1")]
+        count: usize,
+
+        /// Case sensitive matching
+        #[arg(long, short = 'C')]
+        case_sensitive: bool,
+
+        /// Output file to save results
+        #[arg(long, short, default_value = "vanity_results.json")]
+        output: String,
+
+        /// Also write each found key as keypair, base58, hex or binary files in the output's
+        /// directory, or as an ndjson stream next to it
+        #[arg(long, default_value = "json")]
+        format: ExportFormat,
+
+        /// Encrypt private keys and mnemonics in the output file with a passphrase
+        #[arg(long, conflicts_with = "format")]
+        encrypt: bool,
+
+        /// Read the keystore passphrase from this file descriptor instead of prompting
+        #[arg(long)]
+        keystore_passphrase_fd: Option<i32>,
+
+        /// Encrypt the secrets of each hit to an age public key (age1...) or recipients file
+        /// as soon as it is found, so only ciphertext is written or printed
+        #[arg(long, conflicts_with_all = ["encrypt", "format"])]
+        recipient: Option<String>,
+
+        /// File containing the seed phrase (read from stdin if omitted or "-")
+        #[arg(long)]
+        mnemonic_file: Option<String>,
+
+        /// File with one BIP39 passphrase per line to try (empty line = no passphrase)
+        #[arg(long)]
+        passphrases_file: Option<String>,
+
+        /// Number of account indexes i in m/44'/501'/i'/j'
+        #[arg(long, default_value = "2147483648", value_parser = clap::value_parser!(u32).range(1..=1 << 31))]
+        accounts: u32,
+
+        /// Number of change indexes j in m/44'/501'/i'/j' (1 = always 0')
+        #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..=1 << 31))]
+        changes: u32,
+    },
+
+    /// Search for vanity addresses derived with create_with_seed (stake, nonce and other
+    /// system-derived accounts)
+    SearchSeedAddress {
+        /// Base public key that will sign for the derived account
+        #[arg(long)]
+        base: Pubkey,
+
+        /// Program that will own the derived account
+        #[arg(long)]
+        owner: Pubkey,
+
+        /// Pattern that the address should start with
+        #[arg(long, short)]
+        starts_with: Option<String>,
+
+        /// Pattern that the address should end with
+        #[arg(long, short)]
+        ends_with: Option<String>,
+
+        /// Pattern expression such as "^S[oO]l AND (x$ OR @5:??z)", instead of --starts-with/--ends-with
+        #[arg(long, short, conflicts_with_all = ["starts_with", "ends_with"])]
+        pattern: Option<String>,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+
+        /// Number of vanity addresses to generate
+        #[arg(long, short, default_value = "1")]
+        count: usize,
+
+        /// OpenCL device index to use
+        #[arg(long, short)]
+        device: Option<usize>,
+
+        /// Case sensitive matching
+        #[arg(long, short = 'C')]
+        case_sensitive: bool,
+
+        /// Output file to save results
+        #[arg(long, short, default_value = "vanity_results.json")]
+        output: String,
+    },
+
+    /// Search for program-derived addresses (vaults, config accounts, ...) with vanity addresses
+    SearchPda {
+        /// Program the address is derived from
+        #[arg(long)]
+        program_id: Pubkey,
+
+        /// Fixed seed component as <type>:<value> with type str, hex, pubkey, u8, u16, u32
+        /// or u64, or 'var' to place the variable component (default: last)
+        #[arg(long = "seed")]
+        seeds: Vec<SeedComponent>,
+
+        /// Variable component to grind: u64 (counter), bytes:<len> or string:<len>
+        #[arg(long, default_value = "u64")]
+        variable: VariableSeed,
+
+        /// Require this bump instead of the canonical one from find_program_address
+        #[arg(long)]
+        bump: Option<u8>,
+
+        /// Pattern that the address should start with
+        #[arg(long, short)]
+        starts_with: Option<String>,
+
+        /// Pattern that the address should end with
+        #[arg(long, short)]
+        ends_with: Option<String>,
+
+        /// Pattern expression such as "^S[oO]l AND (x$ OR @5:??z)", instead of --starts-with/--ends-with
+        #[arg(long, short, conflicts_with_all = ["starts_with", "ends_with"])]
+        pattern: Option<String>,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+
+        /// Number of vanity addresses to generate
+        #[arg(long, short, default_value = "1")]
+        count: usize,
+
+        /// Case sensitive matching
+        #[arg(long, short = 'C')]
+        case_sensitive: bool,
+
+        /// Output file to save results
+        #[arg(long, short, default_value = "vanity_results.json")]
+        output: String,
+    },
+
+    /// Re-derive a hierarchical keypair from its master secret and index
+    Derive {
+        /// Master secret file used for the search
+        #[arg(long)]
+        master_secret: String,
+
+        /// Derivation index stored in the result
+        #[arg(long)]
+        index: u64,
+    },
+
+    /// Convert saved results into Solana CLI keypair files or other wallet formats
+    Export {
+        /// Results file written by a search
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+
+        /// keypair, base58, hex or binary (one <pubkey> file each), ndjson or json
+        #[arg(long, short, default_value = "keypair")]
+        format: ExportFormat,
+
+        /// Directory for the per-key formats, file for ndjson and json
+        #[arg(long, short)]
+        output: String,
+
+        /// Master secret file to re-derive hierarchical results
+        #[arg(long)]
+        master_secret: Option<String>,
+
+        /// Split each seed into M-of-N Shamir share files such as 3-of-5 instead
+        #[arg(long, conflicts_with = "format")]
+        shamir: Option<ShamirScheme>,
+
+        /// Only export these public keys (repeatable)
+        #[arg(long)]
+        pubkey: Vec<String>,
+
+        /// Read the keystore passphrase from this file descriptor instead of prompting
+        #[arg(long)]
+        keystore_passphrase_fd: Option<i32>,
+    },
+
+    /// Rebuild a keypair from Shamir shares written by export --shamir
+    Combine {
+        /// Shares, or files holding one each
+        #[arg(required = true)]
+        shares: Vec<String>,
+
+        /// Write the keypair in this format instead of printing it
+        #[arg(long, short, default_value = "keypair", requires = "output")]
+        format: ExportFormat,
+
+        /// Directory for the per-key formats, file for ndjson and json
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+
+    /// Decrypt and print selected keys of an encrypted results file
+    Unlock {
+        /// Encrypted results file written with --encrypt
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+
+        /// Only decrypt these public keys (repeatable)
+        #[arg(long)]
+        pubkey: Vec<String>,
+
+        /// Read the keystore passphrase from this file descriptor instead of prompting
+        #[arg(long)]
+        keystore_passphrase_fd: Option<i32>,
+    },
+
+    /// Sign a message with a stored key to prove ownership, without network access
+    SignMessage {
+        /// Public key of the stored result to sign with
+        #[arg(long)]
+        pubkey: String,
+
+        /// Message text
+        #[arg(
+            long,
+            required_unless_present = "message_file",
+            conflicts_with = "message_file"
+        )]
+        message: Option<String>,
+
+        /// File holding the message bytes, - for stdin
+        #[arg(long)]
+        message_file: Option<String>,
+
+        /// Sign it as a Solana off-chain message, with the signing domain and header
+        #[arg(long)]
+        offchain: bool,
+
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+
+        /// Master secret file to re-derive hierarchical results
+        #[arg(long)]
+        master_secret: Option<String>,
+
+        /// Read the keystore passphrase from this file descriptor instead of prompting
+        #[arg(long)]
+        keystore_passphrase_fd: Option<i32>,
+    },
+
+    /// Add a stored key's signature to a serialized transaction, without network access
+    SignTransaction {
+        /// Base64 legacy or versioned transaction, a file holding it, or - for stdin
+        transaction: String,
+
+        /// Public key of the stored result to sign with
+        #[arg(long)]
+        pubkey: String,
+
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+
+        /// Master secret file to re-derive hierarchical results
+        #[arg(long)]
+        master_secret: Option<String>,
+
+        /// Read the keystore passphrase from this file descriptor instead of prompting
+        #[arg(long)]
+        keystore_passphrase_fd: Option<i32>,
+    },
+
+    /// Check a message signature, or every signature of a transaction
+    VerifySignature {
+        /// Public key the message was signed with
+        #[arg(long, required_unless_present = "transaction")]
+        pubkey: Option<Pubkey>,
+
+        /// Base58 signature of the message
+        #[arg(long, required_unless_present = "transaction")]
+        signature: Option<String>,
+
+        /// Message text
+        #[arg(long, conflicts_with = "message_file")]
+        message: Option<String>,
+
+        /// File holding the message bytes, - for stdin
+        #[arg(long)]
+        message_file: Option<String>,
+
+        /// The message was signed as a Solana off-chain message
+        #[arg(long)]
+        offchain: bool,
+
+        /// Base64 transaction whose signatures to check, a file holding it, or - for stdin
+        #[arg(long, conflicts_with_all = ["pubkey", "signature", "message", "message_file", "offchain"])]
+        transaction: Option<String>,
+    },
+
+    /// Check that every stored key gives its public key and still matches its pattern
+    Verify {
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+
+        /// Master secret file to check hierarchical results
+        #[arg(long)]
+        master_secret: Option<String>,
+
+        /// Print a JSON report for every result instead of only the problems
+        #[arg(long)]
+        json: bool,
+
+        /// Read the keystore passphrase from this file descriptor instead of prompting
+        #[arg(long)]
+        keystore_passphrase_fd: Option<i32>,
+    },
+
+    /// List, search, label, delete and merge saved results in a JSON file or SQLite database
+    Results {
+        #[command(subcommand)]
+        command: ResultsCommand,
     },
 
     /// Show available OpenCL devices
@@ -59,47 +790,659 @@ enum Commands {
     Test,
 }
 
+#[derive(Subcommand)]
+enum ResultsCommand {
+    /// One line per result, oldest first
+    List {
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+
+        #[command(flatten)]
+        query: QueryArgs,
+    },
+
+    /// Everything recorded about one result
+    Show {
+        public_key: String,
+
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+    },
+
+    /// Results whose public key, pattern, label or notes contain the text, ignoring case
+    Search {
+        text: String,
+
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+
+        #[command(flatten)]
+        query: QueryArgs,
+    },
+
+    /// Set the label or notes of a result, an empty value clears them
+    Tag {
+        public_key: String,
+
+        #[arg(long, required_unless_present = "note")]
+        label: Option<String>,
+
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+    },
+
+    /// Remove results, including their keys, from the store
+    Delete {
+        #[arg(required = true)]
+        public_keys: Vec<String>,
+
+        /// Results file or database (.db, .sqlite, .sqlite3)
+        #[arg(long, short, default_value = "vanity_results.json")]
+        input: String,
+    },
+
+    /// Add the results of other files or databases, skipping public keys already present
+    Merge {
+        #[arg(required = true)]
+        sources: Vec<String>,
+
+        /// Results file or database to merge into, created if missing
+        #[arg(long, short, default_value = "vanity_results.json")]
+        output: String,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    let show_secrets = cli.show_secrets;
 
     match cli.command {
         Commands::SearchPubkey {
             starts_with,
             ends_with,
+            pattern,
+            chain,
+            matching,
+            case_sensitive,
             count,
             device,
             iteration_bits,
-            case_sensitive,
             output,
+            format,
+            encrypt,
+            keystore_passphrase_fd,
+            recipient,
+            master_secret,
+            start_index,
+            use_mnemonic,
+            word_count,
+            passphrase_file,
+            ata_mint,
+            token_program,
+            match_target,
+            score,
+            progressive,
+            target,
+            top,
+            max_time,
+            max_attempts,
         } => {
+            let keystore = if encrypt {
+                Some(open_or_create(&output, keystore_passphrase_fd)?)
+            } else {
+                None
+            };
+            let recipients = recipient
+                .map(|spec| AgeRecipients::parse(&spec))
+                .transpose()?;
             let mut generator = VanityGenerator::new(
-                starts_with,
-                ends_with,
+                build_pattern(
+                    pattern,
+                    starts_with,
+                    ends_with,
+                    case_sensitive,
+                    chain,
+                    matching,
+                )?,
                 count,
                 device,
                 iteration_bits,
-                case_sensitive,
                 output,
             )?;
 
+            if let Some(path) = master_secret {
+                generator = generator.with_seed_derivation(SeedDerivation::Hierarchical {
+                    master_secret: load_or_create_master_secret(&path)?,
+                    start_index,
+                });
+            }
+
+            if use_mnemonic {
+                let passphrase = match passphrase_file {
+                    Some(path) => read_secret_file(&path)?,
+                    None => Secret::default(),
+                };
+                generator = generator.with_seed_derivation(SeedDerivation::Mnemonic {
+                    word_count: word_count.parse()?,
+                    passphrase,
+                });
+            }
+
+            if let Some(mint) = ata_mint {
+                generator = generator.with_associated_token(AssociatedToken {
+                    mint,
+                    token_program: token_program.0,
+                    target: match_target,
+                });
+            }
+
+            let budget = Budget {
+                max_time: max_time.map(std::time::Duration::from_secs),
+                max_attempts,
+            };
+            if let Some(spec) = score {
+                generator = generator.with_scoring(ScoreConfig::new(
+                    CompositeScorer::parse(&spec, target.as_deref())?,
+                    top,
+                    budget,
+                )?);
+            }
+
+            if let Some(target) = progressive {
+                // Rejects targets that can never appear in an address
+                Pattern::from_affixes(Some(target.clone()), None, case_sensitive)?;
+                generator = generator.with_progressive(target, budget);
+            }
+
+            if let Some(keystore) = keystore {
+                generator = generator.with_keystore(keystore);
+            }
+            if let Some(recipients) = recipients {
+                generator = generator.with_recipients(recipients);
+            }
+            generator
+                .with_format(format)
+                .with_show_secrets(show_secrets)
+                .run()
+                .await?;
+        }
+
+        Commands::SearchDerivationPath {
+            starts_with,
+            ends_with,
+            pattern,
+            matching,
+            case_sensitive,
+            count,
+            output,
+            format,
+            encrypt,
+            keystore_passphrase_fd,
+            recipient,
+            mnemonic_file,
+            passphrases_file,
+            accounts,
+            changes,
+        } => {
+            let phrase = read_mnemonic(mnemonic_file.as_deref())?;
+            let passphrases = match passphrases_file {
+                Some(path) => read_secret_file(&path)?
+                    .expose()
+                    .lines()
+                    .map(|line| Secret::new(line.to_string()))
+                    .collect(),
+                None => vec![Secret::default()],
+            };
+            let wallets = passphrases
+                .into_iter()
+                .map(|passphrase| WalletSeed {
+                    seed: Zeroizing::new(generate_seed_from_seed_phrase_and_passphrase(
+                        phrase.expose(),
+                        passphrase.expose(),
+                    )),
+                    passphrase,
+                })
+                .collect();
+
+            let keystore = if encrypt {
+                Some(open_or_create(&output, keystore_passphrase_fd)?)
+            } else {
+                None
+            };
+            let recipients = recipient
+                .map(|spec| AgeRecipients::parse(&spec))
+                .transpose()?;
+            let mut generator = VanityGenerator::new(
+                build_pattern(
+                    pattern,
+                    starts_with,
+                    ends_with,
+                    case_sensitive,
+                    Chain::Solana,
+                    matching,
+                )?,
+                count,
+                None,
+                20,
+                output,
+            )?
+            .with_seed_derivation(SeedDerivation::WalletPaths {
+                wallets,
+                accounts,
+                changes,
+            })
+            .with_format(format)
+            .with_show_secrets(show_secrets);
+            if let Some(keystore) = keystore {
+                generator = generator.with_keystore(keystore);
+            }
+            if let Some(recipients) = recipients {
+                generator = generator.with_recipients(recipients);
+            }
+
             generator.run().await?;
         }
 
+        Commands::SearchSeedAddress {
+            base,
+            owner,
+            starts_with,
+            ends_with,
+            pattern,
+            matching,
+            case_sensitive,
+            count,
+            device,
+            output,
+        } => {
+            let mut generator = SeedAddressGenerator::new(
+                base,
+                owner,
+                build_pattern(
+                    pattern,
+                    starts_with,
+                    ends_with,
+                    case_sensitive,
+                    Chain::Solana,
+                    matching,
+                )?,
+                count,
+                device,
+                output,
+            )?;
+
+            generator.run()?;
+        }
+
+        Commands::SearchPda {
+            program_id,
+            seeds,
+            variable,
+            bump,
+            starts_with,
+            ends_with,
+            pattern,
+            matching,
+            case_sensitive,
+            count,
+            output,
+        } => {
+            let mut generator = PdaGenerator::new(
+                program_id,
+                seeds,
+                variable,
+                bump,
+                build_pattern(
+                    pattern,
+                    starts_with,
+                    ends_with,
+                    case_sensitive,
+                    Chain::Solana,
+                    matching,
+                )?,
+                count,
+                output,
+            )?;
+
+            generator.run()?;
+        }
+
+        Commands::Derive {
+            master_secret,
+            index,
+        } => {
+            let master_secret = load_master_secret(&master_secret)?;
+            let keypair = derive_hd_keypair(&master_secret, index);
+            println!("Index: {}", index);
+            println!("Public Key: {}", keypair.pubkey());
+            println!(
+                "Private Key: {}",
+                reveal(&Secret::new(keypair.to_base58_string()), show_secrets)
+            );
+        }
+
+        Commands::Export {
+            input,
+            format,
+            output,
+            master_secret,
+            shamir,
+            pubkey,
+            keystore_passphrase_fd,
+        } => {
+            let results = unlock_results(&input, &pubkey, keystore_passphrase_fd)?;
+            if results.is_empty() {
+                bail!("No results in {}", input);
+            }
+            let master_secret = master_secret
+                .map(|path| load_master_secret(&path))
+                .transpose()?;
+            let written = match shamir {
+                Some(scheme) => export_shares(&results, scheme, &output, master_secret.as_deref())?,
+                None => export_results(&results, format, &output, master_secret.as_deref())?,
+            };
+            println!(
+                "Exported {} of {} results to {}",
+                written,
+                results.len(),
+                output
+            );
+            if written < results.len() {
+                println!("Seed and program-derived addresses have no private key to export");
+            }
+        }
+
+        Commands::Combine {
+            shares,
+            format,
+            output,
+        } => {
+            let shares = shares
+                .iter()
+                .map(|arg| Share::read(arg))
+                .collect::<Result<Vec<_>>>()?;
+            let keypair = combine(&shares)?;
+            match output {
+                Some(output) => {
+                    let result = VanityResult {
+                        public_key: keypair.pubkey().to_string(),
+                        private_key: Secret::new(keypair.to_base58_string()),
+                        ..Default::default()
+                    };
+                    export_results(&[result], format, &output, None)?;
+                    println!("✅ Rebuilt {} and wrote it to {}", keypair.pubkey(), output);
+                }
+                None => {
+                    println!("✅ Rebuilt {}", keypair.pubkey());
+                    println!(
+                        "Private Key: {}",
+                        reveal(&Secret::new(keypair.to_base58_string()), show_secrets)
+                    );
+                }
+            }
+        }
+
+        Commands::Unlock {
+            input,
+            pubkey,
+            keystore_passphrase_fd,
+        } => {
+            let results = unlock_results(&input, &pubkey, keystore_passphrase_fd)?;
+            if results.is_empty() {
+                bail!("No results in {}", input);
+            }
+            display_results(&results, show_secrets);
+        }
+
+        Commands::SignMessage {
+            pubkey,
+            message,
+            message_file,
+            offchain,
+            input,
+            master_secret,
+            keystore_passphrase_fd,
+        } => {
+            let message = read_message(message, message_file)?;
+            let master_secret = master_secret
+                .map(|path| load_master_secret(&path))
+                .transpose()?;
+            let keypair = load_signer(
+                &input,
+                &pubkey,
+                master_secret.as_deref(),
+                keystore_passphrase_fd,
+            )?;
+            println!("{}", sign_message(&keypair, &message, offchain)?);
+        }
+
+        Commands::SignTransaction {
+            transaction,
+            pubkey,
+            input,
+            master_secret,
+            keystore_passphrase_fd,
+        } => {
+            let mut transaction = decode_transaction(&transaction)?;
+            let master_secret = master_secret
+                .map(|path| load_master_secret(&path))
+                .transpose()?;
+            let keypair = load_signer(
+                &input,
+                &pubkey,
+                master_secret.as_deref(),
+                keystore_passphrase_fd,
+            )?;
+            let (signature, missing) = sign_transaction(&mut transaction, &keypair)?;
+            eprintln!("Signed by {}: {}", pubkey, signature);
+            if missing > 0 {
+                eprintln!("{} more signatures needed before it can be sent", missing);
+            }
+            println!("{}", encode_transaction(&transaction)?);
+        }
+
+        Commands::VerifySignature {
+            pubkey,
+            signature,
+            message,
+            message_file,
+            offchain,
+            transaction,
+        } => match (transaction, pubkey, signature) {
+            (Some(transaction), _, _) => {
+                let mut invalid = 0;
+                for (signer, valid) in verify_transaction(&decode_transaction(&transaction)?) {
+                    match valid {
+                        Some(true) => println!("✅ {}: valid signature", signer),
+                        Some(false) => {
+                            println!("❌ {}: invalid signature", signer);
+                            invalid += 1;
+                        }
+                        None => println!("⚠️  {}: not signed yet", signer),
+                    }
+                }
+                if invalid > 0 {
+                    bail!("{} transaction signatures are invalid", invalid);
+                }
+            }
+            (None, Some(pubkey), Some(signature)) => {
+                let signature: Signature = signature
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid signature {}: {}", signature, e))?;
+                let message = read_message(message, message_file)?;
+                if !verify_message(&pubkey, &message, &signature, offchain)? {
+                    bail!(
+                        "Signature is not valid for {} and this message{}",
+                        pubkey,
+                        if offchain {
+                            ""
+                        } else {
+                            " (signed with --offchain?)"
+                        }
+                    );
+                }
+                println!("✅ Valid signature by {}", pubkey);
+            }
+            _ => unreachable!("clap requires --pubkey and --signature without --transaction"),
+        },
+
+        Commands::Verify {
+            input,
+            master_secret,
+            json,
+            keystore_passphrase_fd,
+        } => {
+            let (mut results, header) = read_results_file(&input)?;
+            if results.is_empty() {
+                bail!("No results in {}", input);
+            }
+            unseal_results(&mut results, header.as_ref(), keystore_passphrase_fd)?;
+            let master_secret = master_secret
+                .map(|path| load_master_secret(&path))
+                .transpose()?;
+            let verifications = verify_results(&results, master_secret.as_deref());
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&verifications)?);
+            } else {
+                for verification in &verifications {
+                    for failure in &verification.failures {
+                        println!("❌ {}: {}", verification.public_key, failure);
+                    }
+                    for unchecked in &verification.unchecked {
+                        println!(
+                            "⚠️  {}: not checked, {}",
+                            verification.public_key, unchecked
+                        );
+                    }
+                }
+            }
+            let failed = verifications
+                .iter()
+                .filter(|verification| !verification.ok)
+                .count();
+            let partial = verifications
+                .iter()
+                .filter(|verification| verification.ok && !verification.unchecked.is_empty())
+                .count();
+            eprintln!(
+                "Verified {} results in {}: {} passed, {} failed, {} only partly checked",
+                verifications.len(),
+                input,
+                verifications.len() - failed - partial,
+                failed,
+                partial
+            );
+            if failed > 0 {
+                bail!(
+                    "{} of {} results failed verification",
+                    failed,
+                    verifications.len()
+                );
+            }
+        }
+
+        Commands::Results { command } => match command {
+            ResultsCommand::List { input, query } => {
+                print_result_list(&store::query(&input, &query.filter(None)?)?);
+            }
+            ResultsCommand::Show { public_key, input } => {
+                let results = store::query(
+                    &input,
+                    &ResultFilter {
+                        text: Some(public_key.clone()),
+                        ..Default::default()
+                    },
+                )?;
+                let Some(result) = results
+                    .into_iter()
+                    .find(|result| result.public_key == public_key)
+                else {
+                    bail!("{} is not in {}", public_key, input);
+                };
+                display_results(std::slice::from_ref(&result), show_secrets);
+                if let Some(spec) = &result.pattern {
+                    println!("   Pattern Spec: {}", spec);
+                }
+                if let Some(positions) = &result.match_positions {
+                    let spans: Vec<String> = positions
+                        .iter()
+                        .map(|span| format!("{}..{}", span.start, span.end))
+                        .collect();
+                    println!("   Match Positions: {}", spans.join(", "));
+                }
+                if let Some(provenance) = &result.provenance {
+                    println!("   Tool Version: {}", provenance.tool_version);
+                    println!("   Seed Derivation: {}", provenance.seed_derivation);
+                    println!("   Case Sensitive: {}", provenance.case_sensitive);
+                }
+            }
+            ResultsCommand::Search { text, input, query } => {
+                print_result_list(&store::query(&input, &query.filter(Some(text))?)?);
+            }
+            ResultsCommand::Tag {
+                public_key,
+                label,
+                note,
+                input,
+            } => {
+                let _lock = ResultsLock::acquire(&input)?;
+                store::tag(&input, &public_key, label, note)?;
+                println!("Updated {} in {}", public_key, input);
+            }
+            ResultsCommand::Delete { public_keys, input } => {
+                let _lock = ResultsLock::acquire(&input)?;
+                let deleted = store::delete(&input, &public_keys)?;
+                println!("Deleted {} results from {}", deleted, input);
+            }
+            ResultsCommand::Merge { sources, output } => {
+                let _lock = ResultsLock::acquire(&output)?;
+                let added = store::merge(&output, &sources)?;
+                println!("Merged {} new results into {}", added, output);
+            }
+        },
+
         Commands::ShowDevices => {
             let opencl_manager = OpenCLManager::new()?;
             opencl_manager.list_devices()?;
         }
 
         Commands::Test => {
-            test_vanity_generation()?;
+            test_vanity_generation().await?;
         }
     }
 
     Ok(())
 }
 
-fn test_vanity_generation() -> Result<()> {
+/// One line per result for `results list` and `results search`, without secrets
+fn print_result_list(results: &[VanityResult]) {
+    for result in results {
+        let run = result
+            .provenance
+            .as_ref()
+            .map_or("-", |provenance| provenance.run_id.as_str());
+        println!(
+            "{:<44}  {}  {:<16}  {}  {}",
+            result.public_key,
+            result.found_at.format("%Y-%m-%d %H:%M:%S"),
+            run,
+            result.pattern.as_deref().unwrap_or(&result.pattern_matched),
+            result.label.as_deref().unwrap_or("")
+        );
+    }
+    println!("{} results", results.len());
+}
+
+async fn test_vanity_generation() -> Result<()> {
     println!("🧪 Testing vanity address generation...");
 
     // Test 1: Generate a simple keypair and verify it's valid
@@ -114,7 +1457,7 @@ fn test_vanity_generation() -> Result<()> {
 
     // Test 2: Test pattern matching
     println!("\nTest 2: Pattern matching");
-    let test_patterns = vec!["ABC".to_string(), "XYZ".to_string()];
+    let test_patterns = ["ABC".to_string(), "XYZ".to_string()];
     let test_address = "ABC123XYZ456789".to_string();
 
     for pattern in &test_patterns {
@@ -136,7 +1479,6 @@ fn test_vanity_generation() -> Result<()> {
         }
         Err(_) => {
             println!("⚠️  Seed-based generation failed, using fallback");
-            let mut rng = rand::prelude::StdRng::seed_from_u64(seed as u64);
             let kp = solana_sdk::signature::Keypair::new();
             let addr = kp.pubkey().to_string();
             println!("Fallback generation: {}", addr);
@@ -166,6 +1508,1280 @@ fn test_vanity_generation() -> Result<()> {
         Err(e) => println!("❌ OpenCL manager creation failed: {}", e),
     }
 
+    // Test 5: Hierarchical derivation is deterministic and identical on CPU and OpenCL
+    println!("\nTest 5: Hierarchical seed derivation");
+    let master_secret: [u8; 32] = std::array::from_fn(|i| i as u8);
+    let known_seeds = [
+        (
+            0u64,
+            "9f0cd9b94097fe4929918d2b8942b34439574261a35dc50163f06c67d4e48899",
+        ),
+        (
+            1,
+            "3b345d4e3f7a9922d8942f7c4f9c46a36307684beb1c02f98dba9327be8e1617",
+        ),
+        (
+            (1 << 40) + 7,
+            "39a258d07a3e545ccd94450de7ef0faed60cdef856a3699b0db74330c0f48db1",
+        ),
+    ];
+    for (index, expected) in known_seeds {
+        let seed = hex::encode(derive_hd_seed(&master_secret, index));
+        if seed == expected {
+            println!(
+                "✅ CPU seed for index {} matches HMAC-SHA256 test vector",
+                index
+            );
+        } else {
+            println!("❌ CPU seed for index {}: {} != {}", index, seed, expected);
+        }
+    }
+    let rederived = derive_hd_keypair(&master_secret, 42);
+    match solana_sdk::signature::keypair_from_seed(&derive_hd_seed(&master_secret, 42)) {
+        Ok(kp) if kp.pubkey() == rederived.pubkey() => {
+            println!("✅ Keypair for index 42 re-derives to {}", kp.pubkey())
+        }
+        _ => println!("❌ Keypair for index 42 does not re-derive"),
+    }
+    match opencl::OpenCLManager::new() {
+        Ok(manager) if manager.get_device_count() > 0 => {
+            let start_index = (1u64 << 32) - 8; // Cross the 32-bit word boundary
+            let count = 1000;
+            match manager
+                .create_vanity_kernel(0)
+                .and_then(|kernel| kernel.derive_hd_seeds(&master_secret, start_index, count))
+            {
+                Ok(gpu_seeds) => {
+                    let mismatches = gpu_seeds
+                        .iter()
+                        .enumerate()
+                        .filter(|(offset, seed)| {
+                            **seed != derive_hd_seed(&master_secret, start_index + *offset as u64)
+                        })
+                        .count();
+                    if mismatches == 0 {
+                        println!("✅ OpenCL and CPU seeds identical for {} indices", count);
+                    } else {
+                        println!(
+                            "❌ {} of {} OpenCL seeds differ from CPU",
+                            mismatches, count
+                        );
+                    }
+                }
+                Err(e) => println!("❌ OpenCL hierarchical derivation failed: {}", e),
+            }
+        }
+        _ => println!("⚠️  No OpenCL device, skipping CPU/OpenCL seed comparison"),
+    }
+
+    // Test 6: Mnemonic hits keep the BIP39 passphrase they were derived with
+    println!("\nTest 6: Mnemonic search with a passphrase");
+    let path = std::env::temp_dir()
+        .join(format!("vanity-mnemonic-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let generator = VanityGenerator::new(Pattern::parse("", false)?, 1, None, 0, path.clone())?
+        .with_seed_derivation(SeedDerivation::Mnemonic {
+            word_count: 12,
+            passphrase: Secret::new("correct horse".to_string()),
+        });
+    let hit = generator.attempt();
+    drop(generator);
+    std::fs::remove_file(format!("{}.lock", path))?;
+    match hit {
+        Some(result)
+            if result
+                .bip39_passphrase
+                .as_ref()
+                .is_some_and(|passphrase| passphrase.expose() == "correct horse")
+                && verify_results(std::slice::from_ref(&result), None)[0].ok =>
+        {
+            println!("✅ Mnemonic hit with a passphrase records it and re-derives")
+        }
+        _ => println!("❌ Mnemonic hit with a passphrase lost or failed verification"),
+    }
+
+    // Test 7: Wallet path searches walk every passphrase, account and change in order
+    println!("\nTest 7: Derivation paths of a fixed seed phrase");
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    let wallets: Vec<WalletSeed> = ["", "extra"]
+        .into_iter()
+        .map(|passphrase| WalletSeed {
+            seed: Zeroizing::new(generate_seed_from_seed_phrase_and_passphrase(
+                phrase, passphrase,
+            )),
+            passphrase: Secret::new(passphrase.to_string()),
+        })
+        .collect();
+    let order: Vec<_> = (0..6)
+        .map(|index| utils::wallet_path_at(index, 2, 3))
+        .collect();
+    let walk = SeedDerivation::WalletPaths {
+        wallets,
+        accounts: 4,
+        changes: 3,
+    };
+    let mnemonic = SeedDerivation::Mnemonic {
+        word_count: 12,
+        passphrase: Secret::default(),
+    };
+    let pattern = Pattern::parse("^ab", true)?;
+    if order
+        == [
+            (0, 0, 0),
+            (1, 0, 0),
+            (0, 0, 1),
+            (1, 0, 1),
+            (0, 0, 2),
+            (1, 0, 2),
+        ]
+        && walk.keyspace_size() == Some(24)
+        && utils::estimate_work_needed(&pattern, &mnemonic)
+            == (utils::estimate_attempts_needed(&pattern) as f64 * utils::MNEMONIC_COST_FACTOR)
+                as u64
+    {
+        println!("✅ Keyspace, path order and per-attempt cost as estimated");
+    } else {
+        println!(
+            "❌ Keyspace {:?} or path order {:?} wrong",
+            walk.keyspace_size(),
+            order
+        );
+    }
+    let path = std::env::temp_dir()
+        .join(format!("vanity-wallet-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let generator = VanityGenerator::new(Pattern::parse("", false)?, 1, None, 0, path.clone())?
+        .with_seed_derivation(walk);
+    let hits: Vec<_> = (0..2).filter_map(|_| generator.attempt()).collect();
+    drop(generator);
+    std::fs::remove_file(format!("{}.lock", path))?;
+    let expected = solana_sdk::signature::keypair_from_seed_and_derivation_path(
+        &generate_seed_from_seed_phrase_and_passphrase(phrase, "extra"),
+        Some(solana_sdk::derivation_path::DerivationPath::new_bip44(
+            Some(0),
+            Some(0),
+        )),
+    )
+    .ok();
+    match (hits.as_slice(), expected) {
+        ([_, second], Some(expected))
+            if second.public_key == expected.pubkey().to_string()
+                && second.derivation_path.as_deref() == Some("m/44'/501'/0'/0'")
+                && verify_results(&hits, None).iter().all(|check| check.ok) =>
+        {
+            println!("✅ Path and passphrase recorded as wallets import them")
+        }
+        _ => println!("❌ Wallet path hits do not re-derive"),
+    }
+
+    // Test 8: create_with_seed addresses are identical on CPU and OpenCL
+    println!("\nTest 8: Seed address derivation");
+    let run_prefix = *b"TestRun0";
+    let seeds = [
+        seed_address::seed_for_index(&run_prefix, 0),
+        seed_address::seed_for_index(&run_prefix, 62 * 62 + 61),
+    ];
+    if seeds == ["TestRun000000000", "TestRun00000010z"] {
+        println!("✅ Seeds are the run prefix plus a fixed-width base62 index");
+    } else {
+        println!("❌ Unexpected seeds {:?}", seeds);
+    }
+    match opencl::OpenCLManager::new() {
+        Ok(manager) if manager.get_device_count() > 0 => {
+            let base = Pubkey::new_unique();
+            let owner = solana_sdk::stake::program::id();
+            let start_index = 62 * 62 - 100; // Carry into the third counter digit
+            let count = 4096;
+            let address = |index: u64| {
+                let seed = seed_address::seed_for_index(&run_prefix, index);
+                Pubkey::create_with_seed(&base, &seed, &owner).expect("stake program is not a PDA")
+            };
+            let mut byte_value = [0u8; 32];
+            let mut byte_mask = [0u8; 32];
+            byte_value[0] = 0x50;
+            byte_mask[0] = 0xf0;
+            let prefix_a: fn(&Pubkey) -> bool = |address| address.to_string().starts_with('A');
+            let nibble_5: fn(&Pubkey) -> bool = |address| address.to_bytes()[0] & 0xf0 == 0x50;
+            let cases = [
+                ("A", [0; 32], [0; 32], prefix_a),
+                ("", byte_value, byte_mask, nibble_5),
+            ];
+            for (starts_with, value, mask, matches) in cases {
+                let expected: Vec<u64> = (start_index..start_index + count as u64)
+                    .filter(|&index| matches(&address(index)))
+                    .collect();
+                match manager.create_vanity_kernel(0).and_then(|kernel| {
+                    kernel.grind_seed_addresses(
+                        &base.to_bytes(),
+                        &owner.to_bytes(),
+                        &run_prefix,
+                        start_index,
+                        count,
+                        starts_with,
+                        "",
+                        true,
+                        &value,
+                        &mask,
+                    )
+                }) {
+                    Ok(mut hits) => {
+                        hits.sort_unstable();
+                        if hits == expected {
+                            println!(
+                                "✅ OpenCL and CPU agree on {} of {} seed addresses",
+                                hits.len(),
+                                count
+                            );
+                        } else {
+                            println!(
+                                "❌ OpenCL found {} seed addresses, CPU {}",
+                                hits.len(),
+                                expected.len()
+                            );
+                        }
+                    }
+                    Err(e) => println!("❌ OpenCL seed address grinding failed: {}", e),
+                }
+            }
+        }
+        _ => println!("⚠️  No OpenCL device, skipping CPU/OpenCL seed address comparison"),
+    }
+
+    // Test 9: PDAs match the runtime derivation, with the canonical or a fixed bump
+    println!("\nTest 9: Program-derived addresses");
+    let program_id = Pubkey::new_unique();
+    let path = std::env::temp_dir()
+        .join(format!("vanity-pda-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let seeds = vec![
+        "str:vault".parse::<SeedComponent>()?,
+        SeedComponent::Variable,
+    ];
+    let canonical = PdaGenerator::new(
+        program_id,
+        seeds.clone(),
+        "u64".parse::<VariableSeed>()?,
+        None,
+        Pattern::parse("", false)?,
+        1,
+        path.clone(),
+    )?
+    .derive(&7u64.to_le_bytes());
+    let expected = Pubkey::find_program_address(&[b"vault", &7u64.to_le_bytes()], &program_id);
+    std::fs::remove_file(format!("{}.lock", path))?;
+    if canonical == Some(expected) {
+        println!("✅ Canonical bump {} gives {}", expected.1, expected.0);
+    } else {
+        println!("❌ Canonical PDA differs from find_program_address");
+    }
+    let mut mismatches = 0;
+    for bump in [expected.1, 0, 100, 255] {
+        let fixed = PdaGenerator::new(
+            program_id,
+            seeds.clone(),
+            VariableSeed::U64,
+            Some(bump),
+            Pattern::parse("", false)?,
+            1,
+            path.clone(),
+        )?
+        .derive(&7u64.to_le_bytes());
+        let runtime =
+            Pubkey::create_program_address(&[b"vault", &7u64.to_le_bytes(), &[bump]], &program_id)
+                .ok()
+                .map(|address| (address, bump));
+        std::fs::remove_file(format!("{}.lock", path))?;
+        if fixed != runtime {
+            mismatches += 1;
+        }
+    }
+    if mismatches == 0 {
+        println!("✅ Fixed bumps agree with create_program_address");
+    } else {
+        println!(
+            "❌ {} fixed bumps differ from create_program_address",
+            mismatches
+        );
+    }
+
+    // Test 10: Associated token accounts follow the SPL derivation for both token programs
+    println!("\nTest 10: Associated token accounts");
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let associated_token_program =
+        Pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL")?;
+    let mut accounts = Vec::new();
+    for (name, program_id) in [
+        ("spl-token", "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+        ("token-2022", "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"),
+    ] {
+        let token_program = Pubkey::from_str(program_id)?;
+        let associated_token = AssociatedToken {
+            mint,
+            token_program: TokenProgram::from_str(name)?.0,
+            target: MatchTarget::Ata,
+        };
+        let expected = Pubkey::find_program_address(
+            &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+            &associated_token_program,
+        )
+        .0;
+        if associated_token.address(&owner) == expected {
+            println!("✅ {} ATA matches the SPL derivation", name);
+        } else {
+            println!("❌ {} ATA differs from the SPL derivation", name);
+        }
+        accounts.push(expected);
+        let path = std::env::temp_dir()
+            .join(format!("vanity-ata-{}.json", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let generator = VanityGenerator::new(Pattern::parse("", false)?, 1, None, 0, path.clone())?
+            .with_associated_token(associated_token.clone());
+        let hit = generator.attempt();
+        drop(generator);
+        std::fs::remove_file(format!("{}.lock", path))?;
+        match hit {
+            Some(result)
+                if Pubkey::from_str(&result.public_key).is_ok_and(|owner| {
+                    result.associated_token_account
+                        == Some(associated_token.address(&owner).to_string())
+                }) && result.token_program == Some(token_program.to_string())
+                    && verify_results(std::slice::from_ref(&result), None)[0].ok =>
+            {
+                println!("✅ {} hit records the owner's ATA, mint and program", name)
+            }
+            _ => println!("❌ {} hit missing or with the wrong ATA", name),
+        }
+    }
+    if accounts[0] != accounts[1] {
+        println!("✅ Token programs give different ATAs for the same owner and mint");
+    } else {
+        println!("❌ Token program not part of the ATA derivation");
+    }
+
+    // Test 11: Numeric prefix/suffix fast paths agree with plain string matching
+    println!("\nTest 11: Pattern fast paths");
+    let mut checked = 0;
+    let mut mismatches = 0;
+    for i in 0..2000 {
+        let mut key: [u8; 32] = rand::random();
+        if i % 4 == 0 {
+            key[0] = 0; // Leading zero bytes take the string path
+        }
+        let pubkey = Pubkey::new_from_array(key);
+        let address = pubkey.to_string();
+        for len in 1..=6 {
+            for (text, case_sensitive) in [
+                (&address[..len], true),
+                (&address[1..=len], true),
+                (&address[address.len() - len..], true),
+                (&address[..len], false),
+            ] {
+                let text = if case_sensitive {
+                    text.to_string()
+                } else {
+                    text.to_uppercase()
+                };
+                let folded = |s: &str| {
+                    if case_sensitive {
+                        s.to_string()
+                    } else {
+                        s.to_lowercase()
+                    }
+                };
+                for (starts_with, ends_with) in
+                    [(Some(text.clone()), None), (None, Some(text.clone()))]
+                {
+                    let expected = starts_with
+                        .as_ref()
+                        .is_none_or(|p| folded(&address).starts_with(&folded(p)))
+                        && ends_with
+                            .as_ref()
+                            .is_none_or(|p| folded(&address).ends_with(&folded(p)));
+                    let Ok(pattern) = Pattern::from_affixes(starts_with, ends_with, case_sensitive)
+                    else {
+                        continue; // Upper-cased 'o' and friends are not base58
+                    };
+                    checked += 1;
+                    if check_pattern_match(&pubkey, &pattern) != expected {
+                        mismatches += 1;
+                    }
+                }
+            }
+        }
+    }
+    if mismatches == 0 {
+        println!(
+            "✅ {} prefix/suffix checks agree with string matching",
+            checked
+        );
+    } else {
+        println!(
+            "❌ {} of {} prefix/suffix checks disagree",
+            mismatches, checked
+        );
+    }
+    let token_program = associated_token::TOKEN_PROGRAM_ID;
+    for (expr, expected) in [
+        ("^T[oO]ken", true),
+        ("^T[a]ken", false),
+        ("@8:QfeZ AND NOT xyz$", true),
+        ("(^abc OR Ss623) AND [A-D]$", true),
+        ("^?o AND NOT kegQ", false),
+    ] {
+        let matched = check_pattern_match(&token_program, &Pattern::parse(expr, true)?);
+        if matched == expected {
+            println!("✅ '{}' on {}: {}", expr, token_program, matched);
+        } else {
+            println!(
+                "❌ '{}' on {}: {} (expected {})",
+                expr, token_program, matched, expected
+            );
+        }
+    }
+    for expr in ["[ab]x[cd", "^So[l", "[AB"] {
+        match Pattern::parse(expr, true) {
+            Ok(pattern) => println!("❌ '{}' parsed as {}", expr, pattern),
+            Err(e) => println!("✅ '{}' rejected: {}", expr, e),
+        }
+    }
+
+    // Test 12: Word lists match at their position and drop words that cannot occur
+    println!("\nTest 12: Word lists");
+    let path = std::env::temp_dir()
+        .join(format!("vanity-words-{}.txt", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    std::fs::write(
+        &path,
+        "# comment\nmoon\npump\ncat\nZero0\nmoon\nOslo\nsolana\n",
+    )?;
+    let load = |position, case| WordList::load(&path, 4, position, case);
+    let prefix = load(WordPosition::Prefix, WordCase::Exact)?;
+    let suffix = load(WordPosition::Suffix, WordCase::Exact)?;
+    let anywhere = load(WordPosition::Anywhere, WordCase::Exact)?;
+    let any_case = load(WordPosition::Prefix, WordCase::Any)?;
+    std::fs::remove_file(&path)?;
+    let checks = [
+        prefix.len() == 2,
+        prefix.find("pumpXYZ") == Some("pump"),
+        prefix.find("xpump").is_none(),
+        suffix.find("abcmoon") == Some("moon"),
+        suffix.find("moonabc").is_none(),
+        anywhere.find("abmoonxy") == Some("moon"),
+        anywhere.find("abMoonxy").is_none(),
+        any_case.len() == 4,
+        any_case.find("SoLanaxyz") == Some("solana"),
+    ];
+    if checks.iter().all(|&check| check) {
+        println!("✅ Prefix, suffix and anywhere matches, short and non-base58 words dropped");
+    } else {
+        println!("❌ Word list results wrong: {:?}", checks);
+    }
+
+    // Test 13: Score components add up and the top-N list keeps only the best
+    println!("\nTest 13: Best-effort scoring");
+    let scorer = CompositeScorer::parse("prefix,case=0.5,runs=2", Some("SOL"))?;
+    let scores = [scorer.score("SoLxyz"), scorer.score("abbbcd")];
+    if scores == [4.0, 4.0]
+        && CompositeScorer::parse("prefix", None).is_err()
+        && CompositeScorer::parse("length", None).is_err()
+    {
+        println!(
+            "✅ Weighted prefix, case and run components parsed: {}",
+            scorer
+        );
+    } else {
+        println!("❌ Composite scores {:?}", scores);
+    }
+    let scored = |score: f64| VanityResult {
+        public_key: Pubkey::new_unique().to_string(),
+        score: Some(score),
+        ..Default::default()
+    };
+    let mut top = score::TopN::new(2);
+    let offered: Vec<bool> = [1.0, 3.0, 2.0, 1.5, 2.0]
+        .into_iter()
+        .map(|score| top.offer(scored(score)))
+        .collect();
+    let kept: Vec<f64> = top
+        .sorted()
+        .iter()
+        .filter_map(|result| result.score)
+        .collect();
+    let budget = Budget {
+        max_time: None,
+        max_attempts: Some(1),
+    };
+    if offered == [true, true, true, false, false]
+        && kept == [3.0, 2.0]
+        && ScoreConfig::new(CompositeScorer::parse("runs", None)?, 0, budget).is_err()
+    {
+        println!("✅ Top list sorted best first, lowest evicted, ties and --top 0 refused");
+    } else {
+        println!("❌ Top list offered {:?}, kept {:?}", offered, kept);
+    }
+
+    // Test 14: Progressive mode saves one result for every newly reached prefix length
+    println!("\nTest 14: Progressive prefix search");
+    let lengths = [
+        vanity::matching_prefix_len("SoLxyz", "SOLANA", false),
+        vanity::matching_prefix_len("SoLxyz", "SOLANA", true),
+        vanity::matching_prefix_len("xSOL", "SOLANA", false),
+    ];
+    if lengths == [3, 1, 0] {
+        println!("✅ Prefix lengths honour case sensitivity");
+    } else {
+        println!("❌ Prefix lengths {:?}", lengths);
+    }
+    let path = std::env::temp_dir()
+        .join(format!("vanity-progressive-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let budget = Budget {
+        max_time: Some(std::time::Duration::from_secs(60)),
+        max_attempts: None,
+    };
+    let mut generator = VanityGenerator::new(Pattern::parse("", false)?, 1, None, 0, path.clone())?
+        .with_progressive("AB".to_string(), budget);
+    generator.run().await?;
+    drop(generator);
+    let steps = load_results_file(&path)?.0;
+    std::fs::remove_file(&path)?;
+    std::fs::remove_file(format!("{}.lock", path))?;
+    let reached: Vec<usize> = steps
+        .iter()
+        .map(|result| vanity::matching_prefix_len(&result.public_key, "AB", false))
+        .collect();
+    let recorded: Vec<usize> = steps
+        .iter()
+        .map(|result| result.pattern_matched.len())
+        .collect();
+    // The first hit may already match the whole target, so only require strict progress
+    if reached.windows(2).all(|pair| pair[0] < pair[1])
+        && reached.last() == Some(&2)
+        && recorded == reached
+    {
+        println!("✅ One result saved per new prefix length until the target was reached");
+    } else {
+        println!(
+            "❌ Saved prefix lengths {:?}, recorded {:?}",
+            reached, recorded
+        );
+    }
+
+    // Test 15: Approximate matching records the closest text and its distance
+    println!("\nTest 15: Approximate matching");
+    let case_costs: EditCosts = "case=0.25".parse()?;
+    for (expr, max_distance, metric, costs, expected) in [
+        (
+            "^TokXn",
+            1.0,
+            EditMetric::Levenshtein,
+            EditCosts::default(),
+            Some(("Token", 1.0)),
+        ),
+        (
+            "^Tkenk",
+            1.0,
+            EditMetric::Levenshtein,
+            EditCosts::default(),
+            Some(("Tokenk", 1.0)),
+        ),
+        (
+            "^Tkenk",
+            1.0,
+            EditMetric::Hamming,
+            EditCosts::default(),
+            None,
+        ),
+        ("^tokEN", 0.5, EditMetric::Levenshtein, case_costs, None),
+        (
+            "^tokEN",
+            1.0,
+            EditMetric::Levenshtein,
+            case_costs,
+            Some(("Token", 0.75)),
+        ),
+        (
+            "kegXfeZ",
+            1.0,
+            EditMetric::Hamming,
+            EditCosts::default(),
+            Some(("kegQfeZ", 1.0)),
+        ),
+        (
+            "WuBvf9S",
+            2.0,
+            EditMetric::Levenshtein,
+            EditCosts::default(),
+            Some(("WuBvf9S", 0.0)),
+        ),
+    ] {
+        let pattern = Pattern::parse(expr, true)?.with_max_distance(max_distance, metric, costs)?;
+        let matched = check_pattern_match(&token_program, &pattern);
+        let found = pattern.fuzzy_match(&token_program.to_string());
+        let found = found.as_ref().map(|m| (m.text.as_str(), m.distance));
+        if matched == expected.is_some() && (!matched || found == expected) {
+            println!(
+                "✅ '{}' within {} ({:?}): {:?}",
+                expr, max_distance, metric, found
+            );
+        } else {
+            println!(
+                "❌ '{}' within {} ({:?}): {:?} (expected {:?})",
+                expr, max_distance, metric, found, expected
+            );
+        }
+    }
+    let exact = Pattern::parse("^Sol", false)?;
+    let fuzzy_exact = Pattern::parse("^Sol", false)?.with_max_distance(
+        0.0,
+        EditMetric::Levenshtein,
+        EditCosts::default(),
+    )?;
+    if (exact.probability() - fuzzy_exact.probability()).abs() < 1e-12 {
+        println!("✅ Distance 0 estimate equals the exact estimate");
+    } else {
+        println!(
+            "❌ Distance 0 estimate {:.3e} differs from {:.3e}",
+            fuzzy_exact.probability(),
+            exact.probability()
+        );
+    }
+
+    // Test 16: Structural filters only apply to the part of the address outside the match
+    println!("\nTest 16: Structural filters");
+    let nine: CharSet = "9".parse()?;
+    for (expr, exclude, length, forbidden, expected) in [
+        ("^Token", None, Some(43), None, true),
+        ("^Token", None, Some(44), None, false),
+        ("^Token", Some("Ss623"), None, None, false),
+        ("^Token", Some("[1ij][1ij][1ij]"), None, None, true),
+        ("^Token", None, None, Some(nine), false),
+        ("vf9Ss", None, None, Some(nine), true),
+    ] {
+        let excludes = exclude
+            .map(|exclude| Pattern::parse(exclude, true))
+            .into_iter()
+            .collect::<Result<_>>()?;
+        let pattern = Pattern::parse(expr, true)?
+            .with_filters(Filters::new(excludes, None, length, None, forbidden)?);
+        let matched = check_pattern_match(&token_program, &pattern);
+        if matched == expected {
+            println!("✅ '{}' on {}: {}", pattern, token_program, matched);
+        } else {
+            println!(
+                "❌ '{}' on {}: {} (expected {})",
+                pattern, token_program, matched, expected
+            );
+        }
+    }
+
+    // Test 17: Raw byte constraints agree with comparing the key bytes directly
+    println!("\nTest 17: Raw byte matching");
+    // Each check constrains one byte: (prefix, mask, byte index, bits, expected value)
+    let mut mismatches = 0;
+    for (prefix, mask, index, bits, value) in [
+        ("00", None, 0, 0xff, 0x00),
+        ("a", None, 0, 0xf0, 0xa0),
+        ("05", Some("0f"), 0, 0x0f, 0x05),
+        ("0080", Some("00c0"), 1, 0xc0, 0x80),
+    ] {
+        let pattern = Pattern::parse("", false)?.with_bytes(ByteMask::parse(prefix, mask)?);
+        for i in 0..1000 {
+            let mut key: [u8; 32] = rand::random();
+            if i % 2 == 0 {
+                key[0] = 0; // Make the leading zero byte common enough to hit
+            }
+            let pubkey = Pubkey::new_from_array(key);
+            if check_pattern_match(&pubkey, &pattern) != (key[index] & bits == value) {
+                mismatches += 1;
+            }
+        }
+        println!("   {} → 1 in {:.0}", pattern, 1.0 / pattern.probability());
+    }
+    if mismatches == 0 {
+        println!("✅ 4000 raw byte checks agree with direct comparison");
+    } else {
+        println!("❌ {} of 4000 raw byte checks disagree", mismatches);
+    }
+    match ByteMask::parse("ff", Some("f0")) {
+        Err(e) => println!("✅ Rejected prefix outside its mask: {}", e),
+        Ok(_) => println!("❌ Accepted prefix ff with mask f0"),
+    }
+
+    // Test 18: Key ranges follow Pubkey ordering and have exact probabilities
+    println!("\nTest 18: Key ordering constraints");
+    let lower = Pubkey::new_from_array(rand::random());
+    let upper = Pubkey::new_from_array(rand::random());
+    let (lower, upper) = (lower.min(upper), lower.max(upper));
+    let range = KeyRange::greater_than(&lower.to_bytes())
+        .intersect(&KeyRange::less_than(&upper.to_bytes()));
+    let mut mismatches = 0;
+    for _ in 0..10000 {
+        let pubkey = Pubkey::new_from_array(rand::random());
+        if range.matches(&pubkey.to_bytes()) != (lower < pubkey && pubkey < upper) {
+            mismatches += 1;
+        }
+    }
+    for bound in [lower, upper] {
+        if range.matches(&bound.to_bytes()) {
+            mismatches += 1; // Both bounds are exclusive
+        }
+    }
+    if mismatches == 0 {
+        println!("✅ 10002 range checks agree with Pubkey ordering");
+    } else {
+        println!(
+            "❌ {} range checks disagree with Pubkey ordering",
+            mismatches
+        );
+    }
+    for (range, expected) in [
+        (KeyRange::parse("..80")?, 0.5),
+        (KeyRange::parse("10..20")?, 1.0 / 16.0),
+        (KeyRange::parse("0000..0001")?, 2f64.powi(-16)),
+        (KeyRange::greater_than(&[0xff; 32]), 0.0),
+    ] {
+        if range.probability() == expected {
+            println!("✅ {}: {:e}", range, expected);
+        } else {
+            println!(
+                "❌ {}: {:e} (expected {:e})",
+                range,
+                range.probability(),
+                expected
+            );
+        }
+    }
+
+    // Test 19: Other chains' addresses and patterns matched against them
+    println!("\nTest 19: Address formats");
+    let key: [u8; 32] = std::array::from_fn(|i| i as u8);
+    for (chain, expected) in [
+        (Chain::Solana, "1thX6LZfHDZZKUs92febYZhYRcXddmzfzF2NvTkPNE"),
+        (
+            Chain::Aptos,
+            "0xa48b46cfc7b26c4da6d5dd176a84104dabdf394eda11e71880c0c6f42ba43bc3",
+        ),
+        (
+            Chain::Sui,
+            "0x0ddaaec3ffac93977c83c3d7440e9e65663850d4861be2f48532548d0a463336",
+        ),
+        (
+            Chain::Near,
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        ),
+        (
+            Chain::Stellar,
+            "GAAACAQDAQCQMBYIBEFAWDANBYHRAEISCMKBKFQXDAMRUGY4DUPB7JZX",
+        ),
+    ] {
+        let address = chain.format().address(&key);
+        let body = &expected[chain.format().fixed_prefix().len()..];
+        let pattern = Pattern::parse_for(&format!("^{}", &body[..4]), true, chain)?;
+        let matched = check_pattern_match(&Pubkey::new_from_array(key), &pattern);
+        if address == expected && matched {
+            println!("✅ {:?}: {}", chain, address);
+        } else {
+            println!(
+                "❌ {:?}: {} (expected {}, pattern matched: {})",
+                chain, address, expected, matched
+            );
+        }
+    }
+    for (chain, expr, expected) in [
+        (Chain::Near, "^abcd", 16f64.powi(-4)),
+        (Chain::Stellar, "^A", 0.25),
+        (Chain::Stellar, "^E", 0.0),
+        (Chain::Stellar, "^?B", 1.0 / 32.0),
+    ] {
+        let pattern = Pattern::parse_for(expr, true, chain)?;
+        if pattern.probability() == expected {
+            println!("✅ {:?} {}: {:e}", chain, expr, expected);
+        } else {
+            println!(
+                "❌ {:?} {}: {:e} (expected {:e})",
+                chain,
+                expr,
+                pattern.probability(),
+                expected
+            );
+        }
+    }
+    match Pattern::parse_for("^0xg", false, Chain::Aptos) {
+        Err(e) => println!("✅ Rejected non-hex pattern: {}", e),
+        Ok(_) => println!("❌ Accepted a pattern that cannot match a hex address"),
+    }
+
+    // Test 20: Exported key files load back as the same keypair, readable only by the owner
+    println!("\nTest 20: Key export formats");
+    let keypair = solana_sdk::signature::Keypair::new();
+    let master_secret: [u8; 32] = rand::random();
+    let results = [
+        VanityResult {
+            public_key: keypair.pubkey().to_string(),
+            private_key: Secret::new(keypair.to_base58_string()),
+            ..Default::default()
+        },
+        VanityResult {
+            public_key: derive_hd_keypair(&master_secret, 7).pubkey().to_string(),
+            derivation_index: Some(7),
+            ..Default::default()
+        },
+    ];
+    let directory = std::env::temp_dir().join(format!("vanity-export-{}", std::process::id()));
+    let directory = directory.to_string_lossy().into_owned();
+    for format in [
+        ExportFormat::Keypair,
+        ExportFormat::Base58,
+        ExportFormat::Hex,
+        ExportFormat::Binary,
+    ] {
+        let written = export_results(&results, format, &directory, Some(&master_secret))?;
+        let mut loaded = 0;
+        for result in &results {
+            let path = format!(
+                "{}/{}.{}",
+                directory,
+                result.public_key,
+                format.extension().unwrap()
+            );
+            let contents = std::fs::read(&path)?;
+            let bytes = match format {
+                ExportFormat::Keypair => serde_json::from_slice::<Vec<u8>>(&contents)?,
+                ExportFormat::Base58 => {
+                    bs58::decode(String::from_utf8(contents)?.trim()).into_vec()?
+                }
+                ExportFormat::Hex => hex::decode(String::from_utf8(contents)?.trim())?,
+                _ => contents,
+            };
+            let private = {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    std::fs::metadata(&path)?.permissions().mode() & 0o777 == 0o600
+                }
+                #[cfg(not(unix))]
+                {
+                    true
+                }
+            };
+            let restored = solana_sdk::signature::Keypair::from_bytes(&bytes)?;
+            if private && restored.pubkey().to_string() == result.public_key {
+                loaded += 1;
+            }
+        }
+        if written == 2 && loaded == 2 {
+            println!("✅ {:?}: 2 private key files load back", format);
+        } else {
+            println!(
+                "❌ {:?}: wrote {} files, {} load back privately",
+                format, written, loaded
+            );
+        }
+    }
+    std::fs::remove_dir_all(&directory)?;
+
+    // Test 21: Keystore secrets only open with the passphrase and for their own public key
+    println!("\nTest 21: Encrypted keystore");
+    let keystore = Keystore::create("correct horse", 10)?;
+    let path = std::env::temp_dir()
+        .join(format!("vanity-keystore-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let plain = VanityResult {
+        public_key: keypair.pubkey().to_string(),
+        private_key: Secret::new(keypair.to_base58_string()),
+        mnemonic: Some(Secret::new("test mnemonic".to_string())),
+        ..Default::default()
+    };
+    save_encrypted_results(std::slice::from_ref(&plain), &path, &keystore)?;
+    let contents = std::fs::read_to_string(&path)?;
+    let (mut sealed, header) = load_results_file(&path)?;
+    std::fs::remove_file(&path)?;
+    let header = header.ok_or_else(|| anyhow::anyhow!("Keystore header missing"))?;
+    if contents.contains(plain.private_key.expose()) || contents.contains("test mnemonic") {
+        println!("❌ Plaintext secret in the keystore file");
+    } else {
+        println!("✅ Keystore file holds no plaintext secrets");
+    }
+    match Keystore::open(&header, "wrong horse") {
+        Err(e) => println!("✅ Wrong passphrase rejected: {}", e),
+        Ok(_) => println!("❌ Wrong passphrase accepted"),
+    }
+    let mut swapped = sealed[0].clone();
+    swapped.public_key = Pubkey::new_unique().to_string();
+    match Keystore::open(&header, "correct horse")?.unseal(&mut swapped) {
+        Err(e) => println!("✅ Secret moved to another key rejected: {}", e),
+        Ok(_) => println!("❌ Secret opened under another public key"),
+    }
+    Keystore::open(&header, "correct horse")?.unseal(&mut sealed[0])?;
+    if sealed[0].private_key == plain.private_key && sealed[0].mnemonic == plain.mnemonic {
+        println!("✅ Secrets restored with the passphrase");
+    } else {
+        println!("❌ Restored secrets differ");
+    }
+
+    // Test 22: Secrets sealed to an age recipient only open with its identity
+    println!("\nTest 22: age recipient encryption");
+    let identity = age::x25519::Identity::generate();
+    let recipients = AgeRecipients::parse(&identity.to_public().to_string())?;
+    let mut sealed = plain.clone();
+    recipients.seal(&mut sealed)?;
+    let armored = sealed.age_secret.clone().unwrap_or_default();
+    let stored = serde_json::to_string(&sealed)?;
+    if sealed.private_key.is_empty()
+        && sealed.mnemonic.is_none()
+        && !stored.contains(plain.private_key.expose())
+        && !stored.contains("test mnemonic")
+        && armored.starts_with("-----BEGIN AGE ENCRYPTED FILE-----")
+    {
+        println!("✅ Only armored ciphertext left in the result");
+    } else {
+        println!("❌ Plaintext secret left after sealing");
+    }
+    let decrypted = match age::Decryptor::new(age::armor::ArmoredReader::new(armored.as_bytes()))? {
+        age::Decryptor::Recipients(decryptor) => {
+            let mut json = String::new();
+            decryptor
+                .decrypt(std::iter::once(&identity as &dyn age::Identity))?
+                .read_to_string(&mut json)?;
+            json
+        }
+        age::Decryptor::Passphrase(_) => bail!("age file is passphrase-encrypted"),
+    };
+    if decrypted.contains(plain.private_key.expose()) && decrypted.contains("test mnemonic") {
+        println!("✅ Identity decrypts the private key and mnemonic");
+    } else {
+        println!("❌ Decrypted secrets differ");
+    }
+    match AgeRecipients::parse("age1notakey") {
+        Err(e) => println!("✅ Invalid recipient rejected: {}", e),
+        Ok(_) => println!("❌ Invalid recipient accepted"),
+    }
+
+    // Test 23: Any M of N Shamir shares rebuild the key, fewer or altered shares do not
+    println!("\nTest 23: Shamir secret sharing");
+    let scheme: ShamirScheme = "3-of-5".parse()?;
+    let encoded: Vec<String> = split(&keypair, scheme)
+        .iter()
+        .map(|share| share.encode())
+        .collect();
+    let decode = |indexes: &[usize]| {
+        indexes
+            .iter()
+            .map(|&i| Share::decode(&encoded[i]))
+            .collect::<Result<Vec<_>>>()
+    };
+    for indexes in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+        match combine(&decode(&indexes)?) {
+            Ok(rebuilt) if rebuilt.to_bytes() == keypair.to_bytes() => {
+                println!("✅ Shares {:?} rebuild the keypair", indexes)
+            }
+            _ => println!("❌ Shares {:?} do not rebuild the keypair", indexes),
+        }
+    }
+    match combine(&decode(&[0, 3])?) {
+        Err(e) => println!("✅ Below threshold rejected: {}", e),
+        Ok(_) => println!("❌ Two of three shares rebuilt a key"),
+    }
+    let mut typo = encoded[0].clone();
+    let last = if typo.ends_with('2') { "3" } else { "2" };
+    typo.replace_range(typo.len() - 1.., last);
+    match Share::decode(&typo) {
+        Err(e) => println!("✅ Mistyped share rejected: {}", e),
+        Ok(_) => println!("❌ Mistyped share accepted"),
+    }
+    let mut mixed = decode(&[0, 1])?;
+    mixed.extend(split(&keypair, scheme).into_iter().skip(2).take(1));
+    match combine(&mixed) {
+        Err(e) => println!("✅ Share of another split rejected: {}", e),
+        Ok(_) => println!("❌ Shares of two splits rebuilt a key"),
+    }
+
+    // Test 24: Secrets stay off the console and out of files other users can read
+    println!("\nTest 24: Secret hygiene");
+    let secret = Secret::new(keypair.to_base58_string());
+    if !format!("{:?}", secret).contains(secret.expose())
+        && reveal(&secret, false) != secret.expose()
+        && reveal(&secret, true) == secret.expose()
+    {
+        println!("✅ Secrets redacted unless shown on request");
+    } else {
+        println!("❌ Secret visible without --show-secrets");
+    }
+    let restored: VanityResult = serde_json::from_str(&serde_json::to_string(&plain)?)?;
+    if restored.private_key == plain.private_key && restored.mnemonic == plain.mnemonic {
+        println!("✅ Secrets round-trip through the results file format");
+    } else {
+        println!("❌ Secrets changed when saved and loaded");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let path = std::env::temp_dir()
+            .join(format!("vanity-perms-{}.json", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        std::fs::write(&path, "[]")?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))?;
+        save_results(std::slice::from_ref(&plain), &path)?;
+        let mode = std::fs::metadata(&path)?.permissions().mode() & 0o777;
+        std::fs::remove_file(&path)?;
+        if mode == 0o600 {
+            println!("✅ World-readable results file rewritten as 0600");
+        } else {
+            println!("❌ Results file saved with mode {:o}", mode);
+        }
+    }
+
+    // Test 25: Results files survive crashes, duplicates and concurrent runs
+    println!("\nTest 25: Crash-safe results store");
+    let path = std::env::temp_dir()
+        .join(format!("vanity-store-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let stored: Vec<VanityResult> = (0..3)
+        .map(|_| VanityResult {
+            public_key: Pubkey::new_unique().to_string(),
+            ..Default::default()
+        })
+        .collect();
+    save_results(&stored, &path)?;
+    let temporary = std::env::temp_dir().join(format!(
+        ".vanity-store-{}.json.{}.tmp",
+        std::process::id(),
+        std::process::id()
+    ));
+    if !temporary.exists() && load_results_file(&path)?.0.len() == 3 {
+        println!("✅ Atomic save leaves only the complete file");
+    } else {
+        println!("❌ Atomic save left a temporary file or lost results");
+    }
+    let content = std::fs::read_to_string(&path)?;
+    let cut = content.rfind("\"public_key\"").unwrap_or_default() + 20;
+    std::fs::write(&path, &content[..cut])?;
+    let recovered = load_results_file(&path)?.0;
+    let backup = format!("{}.corrupt", path);
+    if recovered.len() == 2 && std::path::Path::new(&backup).exists() {
+        println!("✅ Truncated file recovered up to its last complete result");
+    } else {
+        println!("❌ Recovered {} of 2 complete results", recovered.len());
+    }
+    std::fs::remove_file(&backup)?;
+    let doubled = [stored.clone(), stored.clone()].concat();
+    std::fs::write(&path, serde_json::to_string(&doubled)?)?;
+    let mut unique = load_results_file(&path)?.0;
+    if unique.len() == 3 && !add_result(&mut unique, stored[0].clone()) {
+        println!("✅ Duplicate public keys rejected");
+    } else {
+        println!("❌ Duplicate public keys kept");
+    }
+    let first = ResultsLock::acquire(&path)?;
+    let second = ResultsLock::acquire(&path);
+    drop(first);
+    let third = ResultsLock::acquire(&path);
+    std::fs::remove_file(&path)?;
+    std::fs::remove_file(format!("{}.lock", path))?;
+    match (second, third) {
+        (Err(e), Ok(_)) => println!("✅ Second run on the same output refused: {}", e),
+        _ => println!("❌ Results lock not exclusive or not released"),
+    }
+
+    // Test 26: Results files carry a schema version and per-result provenance
+    println!("\nTest 26: Versioned results schema");
+    let path = std::env::temp_dir()
+        .join(format!("vanity-schema-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    std::fs::write(&path, serde_json::to_string(&stored[..2])?)?;
+    let migrated = load_results_file(&path)?.0;
+    save_results(&migrated, &path)?;
+    let content: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    if migrated.len() == 2 && content["schema_version"] == RESULTS_SCHEMA_VERSION {
+        println!(
+            "✅ Version 1 array migrated to schema {}",
+            RESULTS_SCHEMA_VERSION
+        );
+    } else {
+        println!("❌ Version 1 array not migrated");
+    }
+    let provenance = Provenance::new("0123456789abcdef", "opencl", "random", false);
+    let tagged = VanityResult {
+        public_key: Pubkey::new_unique().to_string(),
+        pattern: Some("^ab & xy$".to_string()),
+        match_positions: Some(vec![0..2, 42..44]),
+        provenance: Some(provenance.clone()),
+        ..Default::default()
+    };
+    save_results(&[tagged], &path)?;
+    let loaded = load_results_file(&path)?.0;
+    if loaded[0].provenance.as_ref() == Some(&provenance)
+        && loaded[0].match_positions == Some(vec![0..2, 42..44])
+    {
+        println!("✅ Provenance and match positions round-trip");
+    } else {
+        println!("❌ Provenance or match positions lost");
+    }
+    std::fs::write(
+        &path,
+        format!(
+            "{{\"schema_version\": {}, \"entries\": []}}",
+            RESULTS_SCHEMA_VERSION + 1
+        ),
+    )?;
+    let newer = load_results_file(&path);
+    std::fs::remove_file(&path)?;
+    match newer {
+        Err(e) => println!("✅ Newer schema refused: {}", e),
+        Ok(_) => println!("❌ Newer schema loaded"),
+    }
+
+    // Test 27: SQLite results store and the results subcommands
+    println!("\nTest 27: SQLite results store");
+    let database = std::env::temp_dir()
+        .join(format!("vanity-store-{}.db", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let json = std::env::temp_dir()
+        .join(format!("vanity-merge-{}.json", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
+    let mut older = stored.clone();
+    for result in &mut older {
+        result.found_at = chrono::Utc::now();
+    }
+    older[0].found_at = "2024-01-01T00:00:00Z".parse()?;
+    older[0].provenance = Some(provenance.clone());
+    save_results(&older, &database)?;
+    let run = ResultFilter {
+        run_id: Some(provenance.run_id.clone()),
+        ..Default::default()
+    };
+    let since = ResultFilter {
+        since: Some(parse_date("2025-01-01")?),
+        limit: Some(5),
+        ..Default::default()
+    };
+    if load_results_file(&database)?.0.len() == 3
+        && store::query(&database, &run)?.len() == 1
+        && store::query(&database, &since)?.len() == 2
+    {
+        println!("✅ Database round-trips and filters by run and date");
+    } else {
+        println!("❌ Database lost results or filtered wrongly");
+    }
+    store::tag(
+        &database,
+        &older[1].public_key,
+        Some("treasury".to_string()),
+        None,
+    )?;
+    let mut labelled = older[2].clone();
+    labelled.notes = Some("from the build box".to_string());
+    save_results(&[labelled, older[1].clone()], &json)?;
+    let added = store::merge(&database, std::slice::from_ref(&json))?;
+    let search = ResultFilter {
+        text: Some("BUILD BOX".to_string()),
+        ..Default::default()
+    };
+    let found = store::query(&database, &search)?;
+    let kept = store::query(
+        &database,
+        &ResultFilter {
+            label: Some("treasury".to_string()),
+            ..Default::default()
+        },
+    )?;
+    if added == 0 && found.len() == 1 && kept.len() == 1 {
+        println!("✅ Merge deduplicates and keeps labels and notes");
+    } else {
+        println!("❌ Merge added {} results or lost labels and notes", added);
+    }
+    let deleted = store::delete(&database, &[older[0].public_key.clone()])?;
+    let missing = store::delete(&database, &["missing".to_string()]);
+    let remaining = load_results_file(&database)?.0.len();
+    std::fs::remove_file(&database)?;
+    std::fs::remove_file(&json)?;
+    if deleted == 1 && missing.is_err() && remaining == 2 {
+        println!("✅ Delete removes only the given public keys");
+    } else {
+        println!("❌ Delete removed the wrong results");
+    }
+
+    // Test 28: Stored keys are re-derived and re-matched against their pattern
+    println!("\nTest 28: Result verification");
+    let keypair = solana_sdk::signature::Keypair::new();
+    let address = keypair.pubkey().to_string();
+    let genuine = VanityResult {
+        public_key: address.clone(),
+        private_key: Secret::new(keypair.to_base58_string()),
+        pattern: Some(format!("^{}", &address[..2])),
+        provenance: Some(Provenance::new("0", "cpu", "random", true)),
+        ..Default::default()
+    };
+    let mut swapped = genuine.clone();
+    swapped.public_key = Pubkey::new_unique().to_string();
+    let mut repatterned = genuine.clone();
+    repatterned.pattern = Some(format!("{}$", &address[..3]));
+    let mut summarized = genuine.clone();
+    summarized.pattern = Some(format!("^{}, filtered by max run 3", &address[..2]));
+    let hierarchical = VanityResult {
+        public_key: derive_hd_keypair(&[7; 32], 3).pubkey().to_string(),
+        derivation_index: Some(3),
+        ..Default::default()
+    };
+    let checks = [
+        verify_results(std::slice::from_ref(&genuine), None)[0].ok,
+        !verify_results(&[swapped], None)[0].ok,
+        !verify_results(&[repatterned], None)[0].ok,
+        verify_results(&[summarized], None)[0].unchecked.len() == 1,
+        verify_results(std::slice::from_ref(&hierarchical), Some(&[7; 32]))[0].ok,
+        !verify_results(&[hierarchical], Some(&[8; 32]))[0].ok,
+        !verify_results(&[genuine.clone(), genuine], None)[0].ok,
+    ];
+    if checks.iter().all(|&check| check) {
+        println!("✅ Keys, derivations, patterns and duplicates verified");
+    } else {
+        println!("❌ Verification results wrong: {:?}", checks);
+    }
+
+    // Test 29: Offline message and transaction signatures round-trip
+    println!("\nTest 29: Offline signing");
+    let payer = solana_sdk::signature::Keypair::new();
+    let recipient = solana_sdk::signature::Keypair::new();
+    let message = b"I own this address";
+    let raw = sign_message(&payer, message, false)?;
+    let offchain = sign_message(&payer, message, true)?;
+    let transfer =
+        solana_sdk::system_instruction::transfer(&payer.pubkey(), &recipient.pubkey(), 1);
+    let unsigned = solana_sdk::transaction::VersionedTransaction::from(
+        solana_sdk::transaction::Transaction::new_with_payer(&[transfer], Some(&payer.pubkey())),
+    );
+    let mut transaction = decode_transaction(&encode_transaction(&unsigned)?)?;
+    let (_, missing) = sign_transaction(&mut transaction, &payer)?;
+    let checks = [
+        verify_message(&payer.pubkey(), message, &raw, false)?,
+        verify_message(&payer.pubkey(), message, &offchain, true)?,
+        !verify_message(&payer.pubkey(), message, &offchain, false)?,
+        !verify_message(&payer.pubkey(), b"I own that address", &raw, false)?,
+        missing == 0,
+        verify_transaction(&transaction) == vec![(payer.pubkey(), Some(true))],
+        verify_transaction(&unsigned) == vec![(payer.pubkey(), None)],
+        sign_transaction(&mut transaction.clone(), &recipient).is_err(),
+    ];
+    if checks.iter().all(|&check| check) {
+        println!("✅ Raw, off-chain and transaction signatures verified");
+    } else {
+        println!("❌ Signing results wrong: {:?}", checks);
+    }
+
     println!("\n🎉 All tests completed!");
     Ok(())
 }
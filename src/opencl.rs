@@ -4,7 +4,6 @@ use rand::Rng;
 use std::collections::HashMap;
 
 pub struct OpenCLManager {
-    platforms: Vec<Platform>,
     devices: Vec<Device>,
     contexts: HashMap<usize, Context>,
     queues: HashMap<usize, Queue>,
@@ -17,9 +16,9 @@ impl OpenCLManager {
         let mut contexts = HashMap::new();
         let mut queues = HashMap::new();
 
-        for (_platform_idx, platform) in platforms.iter().enumerate() {
+        for platform in platforms.iter() {
             let platform_devices = Device::list(platform, None)?;
-            for (_device_idx, device) in platform_devices.iter().enumerate() {
+            for device in platform_devices.iter() {
                 let global_idx = devices.len();
                 devices.push(*device);
 
@@ -36,7 +35,6 @@ impl OpenCLManager {
         }
 
         Ok(OpenCLManager {
-            platforms,
             devices,
             contexts,
             queues,
@@ -77,6 +75,15 @@ impl OpenCLManager {
         Ok(())
     }
 
+    /// Device name as reported by the driver, for result provenance
+    pub fn device_name(&self, idx: usize) -> Option<String> {
+        let device = self.devices.get(idx)?;
+        device
+            .info(DeviceInfo::Name)
+            .ok()
+            .map(|name| name.to_string())
+    }
+
     pub fn get_device_count(&self) -> usize {
         self.devices.len()
     }
@@ -121,85 +128,19 @@ impl OpenCLManager {
 
         Ok(VanityKernel {
             kernel,
+            program,
             queue: queue.clone(),
-            context: context.clone(),
         })
     }
 }
 
 pub struct VanityKernel {
     kernel: Kernel,
+    program: Program,
     queue: Queue,
-    context: Context,
 }
 
 impl VanityKernel {
-    pub fn generate_keys(
-        &self,
-        seeds: &[u32],
-        starts_with: &str,
-        ends_with: &str,
-        case_sensitive: bool,
-    ) -> Result<Vec<u8>> {
-        let seed_buffer = Buffer::<u32>::builder()
-            .queue(self.queue.clone())
-            .flags(MemFlags::new().read_only().copy_host_ptr())
-            .len(seeds.len())
-            .copy_host_slice(seeds)
-            .build()?;
-
-        let result_buffer = Buffer::<u8>::builder()
-            .queue(self.queue.clone())
-            .flags(MemFlags::new().write_only())
-            .len(seeds.len() * 64) // 32 bytes for public key + 32 bytes for private key
-            .build()?;
-
-        // Always allocate at least 1 byte for pattern buffers
-        let starts_with_bytes = if starts_with.is_empty() {
-            &[0u8][..]
-        } else {
-            starts_with.as_bytes()
-        };
-        let starts_with_buffer = Buffer::<u8>::builder()
-            .queue(self.queue.clone())
-            .flags(MemFlags::new().read_only().copy_host_ptr())
-            .len(starts_with_bytes.len())
-            .copy_host_slice(starts_with_bytes)
-            .build()?;
-
-        let ends_with_bytes = if ends_with.is_empty() {
-            &[0u8][..]
-        } else {
-            ends_with.as_bytes()
-        };
-        let ends_with_buffer = Buffer::<u8>::builder()
-            .queue(self.queue.clone())
-            .flags(MemFlags::new().read_only().copy_host_ptr())
-            .len(ends_with_bytes.len())
-            .copy_host_slice(ends_with_bytes)
-            .build()?;
-
-        // Set kernel arguments
-        self.kernel.set_arg(0, &seed_buffer)?;
-        self.kernel.set_arg(1, &result_buffer)?;
-        self.kernel.set_arg(2, &starts_with_buffer)?;
-        self.kernel.set_arg(3, &(starts_with.len() as u32))?;
-        self.kernel.set_arg(4, &ends_with_buffer)?;
-        self.kernel.set_arg(5, &(ends_with.len() as u32))?;
-        self.kernel
-            .set_arg(6, &(if case_sensitive { 1u32 } else { 0u32 }))?;
-
-        // Execute kernel
-        unsafe {
-            self.kernel.enq()?;
-        }
-
-        let mut results = vec![0u8; seeds.len() * 64];
-        result_buffer.read(&mut results).enq()?;
-
-        Ok(results)
-    }
-
     pub fn generate_seeds(&self, num_seeds: usize) -> Result<Vec<u32>> {
         // Generate base seeds with better distribution
         let mut base_seeds = Vec::new();
@@ -224,14 +165,13 @@ impl VanityKernel {
 
         // Calculate optimal work group size
         let work_group_size = 256;
-        let global_work_size =
-            ((num_seeds + 3) / 4 + work_group_size - 1) / work_group_size * work_group_size;
+        let global_work_size = num_seeds.div_ceil(4).div_ceil(work_group_size) * work_group_size;
 
         // Set kernel arguments
         self.kernel.set_arg(0, &base_seeds_buffer)?;
         self.kernel.set_arg(1, &output_seeds_buffer)?;
-        self.kernel.set_arg(2, &(4u32))?; // 4 seeds per work item
-        self.kernel.set_arg(3, &(num_seeds as u32))?; // total_seeds_needed
+        self.kernel.set_arg(2, 4u32)?; // 4 seeds per work item
+        self.kernel.set_arg(3, num_seeds as u32)?; // total_seeds_needed
 
         // Execute kernel with optimized work group size
         unsafe {
@@ -248,4 +188,149 @@ impl VanityKernel {
 
         Ok(seeds)
     }
+
+    /// Derives hierarchical seeds for `count` consecutive indices starting at `start_index`
+    pub fn derive_hd_seeds(
+        &self,
+        master_secret: &[u8; 32],
+        start_index: u64,
+        count: usize,
+    ) -> Result<Vec<[u8; 32]>> {
+        let master_words: Vec<u32> = master_secret
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        let master_buffer = Buffer::<u32>::builder()
+            .queue(self.queue.clone())
+            .flags(MemFlags::new().read_only().copy_host_ptr())
+            .len(master_words.len())
+            .copy_host_slice(&master_words)
+            .build()?;
+
+        let output_buffer = Buffer::<u32>::builder()
+            .queue(self.queue.clone())
+            .flags(MemFlags::new().write_only())
+            .len(count * 8)
+            .build()?;
+
+        let work_group_size = 256;
+        let global_work_size = count.div_ceil(work_group_size) * work_group_size;
+
+        let kernel = Kernel::builder()
+            .program(&self.program)
+            .name("derive_hd_seeds")
+            .queue(self.queue.clone())
+            .global_work_size(global_work_size)
+            .local_work_size(work_group_size)
+            .arg(&master_buffer)
+            .arg(start_index)
+            .arg(&output_buffer)
+            .arg(count as u32)
+            .build()?;
+
+        unsafe {
+            kernel.enq()?;
+        }
+
+        let mut words = vec![0u32; count * 8];
+        output_buffer.read(&mut words).enq()?;
+
+        Ok(words
+            .chunks_exact(8)
+            .map(|seed_words| {
+                let mut seed = [0u8; 32];
+                for (bytes, word) in seed.chunks_exact_mut(4).zip(seed_words) {
+                    bytes.copy_from_slice(&word.to_be_bytes());
+                }
+                seed
+            })
+            .collect())
+    }
+
+    /// Grinds `count` create_with_seed seeds starting at `start_index`, returning matching indexes
+    #[allow(clippy::too_many_arguments)]
+    pub fn grind_seed_addresses(
+        &self,
+        base: &[u8; 32],
+        owner: &[u8; 32],
+        run_prefix: &[u8; 8],
+        start_index: u64,
+        count: usize,
+        starts_with: &str,
+        ends_with: &str,
+        case_sensitive: bool,
+        byte_value: &[u8; 32],
+        byte_mask: &[u8; 32],
+    ) -> Result<Vec<u64>> {
+        let max_hits = 1024;
+        let input_buffer = |bytes: &[u8]| {
+            // Always allocate at least 1 byte so empty patterns still get a valid buffer
+            let bytes = if bytes.is_empty() { &[0u8][..] } else { bytes };
+            Buffer::<u8>::builder()
+                .queue(self.queue.clone())
+                .flags(MemFlags::new().read_only().copy_host_ptr())
+                .len(bytes.len())
+                .copy_host_slice(bytes)
+                .build()
+        };
+
+        let base_buffer = input_buffer(base)?;
+        let owner_buffer = input_buffer(owner)?;
+        let prefix_buffer = input_buffer(run_prefix)?;
+        let starts_with_buffer = input_buffer(starts_with.as_bytes())?;
+        let ends_with_buffer = input_buffer(ends_with.as_bytes())?;
+        let byte_value_buffer = input_buffer(byte_value)?;
+        let byte_mask_buffer = input_buffer(byte_mask)?;
+
+        let hits_buffer = Buffer::<u64>::builder()
+            .queue(self.queue.clone())
+            .flags(MemFlags::new().write_only())
+            .len(max_hits)
+            .build()?;
+        let hit_count_buffer = Buffer::<u32>::builder()
+            .queue(self.queue.clone())
+            .flags(MemFlags::new().read_write().copy_host_ptr())
+            .len(1)
+            .copy_host_slice(&[0u32])
+            .build()?;
+
+        let work_group_size = 256;
+        let global_work_size = count.div_ceil(work_group_size) * work_group_size;
+
+        let kernel = Kernel::builder()
+            .program(&self.program)
+            .name("grind_seed_addresses")
+            .queue(self.queue.clone())
+            .global_work_size(global_work_size)
+            .local_work_size(work_group_size)
+            .arg(&base_buffer)
+            .arg(&owner_buffer)
+            .arg(&prefix_buffer)
+            .arg(start_index)
+            .arg(count as u32)
+            .arg(&starts_with_buffer)
+            .arg(starts_with.len() as u32)
+            .arg(&ends_with_buffer)
+            .arg(ends_with.len() as u32)
+            .arg(if case_sensitive { 1u32 } else { 0u32 })
+            .arg(&byte_value_buffer)
+            .arg(&byte_mask_buffer)
+            .arg(&hits_buffer)
+            .arg(&hit_count_buffer)
+            .arg(max_hits as u32)
+            .build()?;
+
+        unsafe {
+            kernel.enq()?;
+        }
+
+        let mut hit_count = [0u32];
+        hit_count_buffer.read(&mut hit_count[..]).enq()?;
+        let mut hits = vec![0u64; max_hits];
+        hits_buffer.read(&mut hits).enq()?;
+        hits.truncate((hit_count[0] as usize).min(max_hits));
+
+        Ok(hits)
+    }
 }
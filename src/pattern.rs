@@ -0,0 +1,931 @@
+use anyhow::{anyhow, bail, Result};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::bytes::{ByteMask, KeyRange};
+use crate::chain::{AddressFormat, Chain};
+use crate::filter::Filters;
+use crate::fuzzy::{EditCosts, EditMetric, FuzzyMatch, FuzzyTerm};
+use crate::wordlist::WordList;
+
+/// Base58 alphabet used by Solana addresses
+pub const BASE58_ALPHABET: &[u8; 58] =
+    b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encoded lengths of a 32-byte key whose first byte is non-zero
+const ENCODED_LENGTHS: [usize; 2] = [43, 44];
+
+/// Most literal strings a term is expanded into for the numeric fast paths
+const MAX_EXPANSIONS: usize = 64;
+
+/// Longest suffix whose modulus 58^k keeps the u128 reduction from overflowing
+const MAX_RESIDUE_LEN: usize = 20;
+
+/// Value of each ASCII character as a base58 digit, or 0xff
+const DIGITS: [u8; 128] = {
+    let mut digits = [0xff; 128];
+    let mut i = 0;
+    while i < BASE58_ALPHABET.len() {
+        digits[BASE58_ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    digits
+};
+
+fn digit(c: u8) -> Option<u8> {
+    DIGITS
+        .get(c as usize)
+        .copied()
+        .filter(|&digit| digit != 0xff)
+}
+
+/// Set of ASCII characters accepted at one position
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct CharSet(u128);
+
+impl CharSet {
+    /// Every base58 character
+    pub fn any() -> Self {
+        Self::of(BASE58_ALPHABET)
+    }
+
+    /// Every character of an address alphabet
+    pub fn of(alphabet: &[u8]) -> Self {
+        Self::from_chars(alphabet.iter().copied(), alphabet)
+    }
+
+    /// Characters outside the alphabet are dropped
+    fn from_chars(chars: impl IntoIterator<Item = u8>, alphabet: &[u8]) -> Self {
+        CharSet(
+            chars
+                .into_iter()
+                .filter(|c| alphabet.contains(c))
+                .fold(0, |bits, c| bits | 1 << c),
+        )
+    }
+
+    pub fn contains(&self, c: u8) -> bool {
+        c < 128 && self.0 >> c & 1 == 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn chars(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..128u8).filter(|&c| self.contains(c))
+    }
+
+    /// Base58 digit values of the characters, for the numeric fast paths
+    fn digits(&self) -> impl Iterator<Item = u8> + '_ {
+        self.chars().filter_map(digit)
+    }
+
+    pub fn difference(&self, other: &CharSet) -> CharSet {
+        CharSet(self.0 & !other.0)
+    }
+
+    /// Fraction of the base58 alphabet in the set
+    pub fn fraction(&self) -> f64 {
+        self.len() as f64 / BASE58_ALPHABET.len() as f64
+    }
+}
+
+impl fmt::Display for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chars: String = self.chars().map(char::from).collect();
+        write!(f, "[{}]", chars)
+    }
+}
+
+/// Class such as `a-z1-9` or `[^xyz]`, with or without the brackets
+impl FromStr for CharSet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let class = s
+            .strip_prefix('[')
+            .and_then(|class| class.strip_suffix(']'))
+            .unwrap_or(s);
+        match parse_class(class.as_bytes(), BASE58_ALPHABET) {
+            Some(set) if set.len() > 0 => Ok(set),
+            _ => bail!("'{}' is not a character class with base58 characters", s),
+        }
+    }
+}
+
+/// Where in the address a term has to appear
+#[derive(Clone, Copy, PartialEq)]
+pub enum Anchor {
+    Prefix,
+    Suffix,
+    Offset(usize),
+    Anywhere,
+}
+
+/// Numeric shortcut that decides a term without base58-encoding the key
+pub enum FastPath {
+    /// Big-endian `[lo, hi)` ranges of the key, one per expanded prefix and encoded length
+    PrefixRanges(Vec<([u8; 33], [u8; 33])>),
+    /// Key modulo 58^k for every expanded suffix of length k
+    SuffixResidues { modulus: u128, residues: Vec<u128> },
+}
+
+/// One anchored run of character sets, e.g. `^S[oO]l` or `@3:??x`
+pub struct Term {
+    pub anchor: Anchor,
+    pub sets: Vec<CharSet>,
+    /// Body as written, when it is a plain literal without classes or wildcards
+    literal: Option<String>,
+    pub fast_path: Option<FastPath>,
+}
+
+impl Term {
+    /// Decides the term from the key bytes, or None if it needs the encoded string.
+    /// Only valid for keys without a leading zero byte, which always encode to 43-44 characters.
+    pub fn match_numeric(&self, key: &[u8; 32]) -> Option<bool> {
+        match self.fast_path.as_ref()? {
+            FastPath::PrefixRanges(ranges) => {
+                let mut value = [0u8; 33];
+                value[1..].copy_from_slice(key);
+                Some(ranges.iter().any(|(lo, hi)| *lo <= value && value < *hi))
+            }
+            FastPath::SuffixResidues { modulus, residues } => {
+                let residue = key
+                    .iter()
+                    .fold(0u128, |r, &byte| (r * 256 + byte as u128) % modulus);
+                Some(residues.contains(&residue))
+            }
+        }
+    }
+
+    pub fn match_str(&self, address: &[u8]) -> bool {
+        self.find(address).is_some()
+    }
+
+    /// Position of the first match in the encoded address
+    fn find(&self, address: &[u8]) -> Option<Range<usize>> {
+        let matches_at = |start: usize| {
+            address
+                .get(start..start + self.sets.len())
+                .is_some_and(|window| {
+                    window
+                        .iter()
+                        .zip(&self.sets)
+                        .all(|(&c, set)| set.contains(c))
+                })
+        };
+        let start = match self.anchor {
+            Anchor::Prefix => Some(0).filter(|&start| matches_at(start)),
+            Anchor::Suffix => address
+                .len()
+                .checked_sub(self.sets.len())
+                .filter(|&start| matches_at(start)),
+            Anchor::Offset(offset) => Some(offset).filter(|&start| matches_at(start)),
+            Anchor::Anywhere => (0..address.len()).find(|&start| matches_at(start)),
+        }?;
+        Some(start..start + self.sets.len())
+    }
+
+    fn probability(&self, format: &dyn AddressFormat) -> f64 {
+        let start = match self.anchor {
+            Anchor::Prefix => Some(0),
+            Anchor::Suffix => Some(format.max_len().saturating_sub(self.sets.len())),
+            Anchor::Offset(offset) => Some(offset),
+            Anchor::Anywhere => None,
+        };
+        let p: f64 = self
+            .sets
+            .iter()
+            .enumerate()
+            .map(|(i, set)| format.char_probability(start.map(|start| start + i), set))
+            .product();
+        match self.anchor {
+            Anchor::Anywhere => {
+                let positions = (format.max_len() + 1).saturating_sub(self.sets.len());
+                1.0 - (1.0 - p).powi(positions as i32)
+            }
+            _ => p,
+        }
+    }
+
+    /// Every digit string the sets accept, or None if there are too many
+    fn expansions(&self) -> Option<Vec<Vec<u8>>> {
+        let count = self
+            .sets
+            .iter()
+            .try_fold(1usize, |count, set| count.checked_mul(set.len()))?;
+        if count > MAX_EXPANSIONS {
+            return None;
+        }
+        Some(self.sets.iter().fold(vec![Vec::new()], |prefixes, set| {
+            prefixes
+                .iter()
+                .flat_map(|prefix| {
+                    set.digits().map(move |digit| {
+                        let mut next = prefix.clone();
+                        next.push(digit);
+                        next
+                    })
+                })
+                .collect()
+        }))
+    }
+
+    fn compile(&mut self) {
+        self.fast_path = match self.anchor {
+            Anchor::Prefix if self.sets.len() <= ENCODED_LENGTHS[0] => {
+                self.expansions().map(|expansions| {
+                    let mut ranges = Vec::new();
+                    // A key without leading zero bytes never encodes to a leading '1'
+                    for digits in expansions.iter().filter(|digits| digits[0] != 0) {
+                        for length in ENCODED_LENGTHS {
+                            let mut lo = [0u8; 33];
+                            let mut hi = [0u8; 33];
+                            for &digit in digits {
+                                mul_add(&mut lo, 58, digit);
+                            }
+                            hi.copy_from_slice(&lo);
+                            mul_add(&mut hi, 1, 1);
+                            for _ in digits.len()..length {
+                                mul_add(&mut lo, 58, 0);
+                                mul_add(&mut hi, 58, 0);
+                            }
+                            ranges.push((lo, hi));
+                        }
+                    }
+                    FastPath::PrefixRanges(ranges)
+                })
+            }
+            Anchor::Suffix if self.sets.len() <= MAX_RESIDUE_LEN => {
+                self.expansions()
+                    .map(|expansions| FastPath::SuffixResidues {
+                        modulus: 58u128.pow(self.sets.len() as u32),
+                        residues: expansions
+                            .iter()
+                            .map(|digits| {
+                                digits
+                                    .iter()
+                                    .fold(0u128, |value, &digit| value * 58 + digit as u128)
+                            })
+                            .collect(),
+                    })
+            }
+            _ => None,
+        };
+    }
+}
+
+/// `value = value * factor + addend` on a 33-byte big-endian integer
+fn mul_add(value: &mut [u8; 33], factor: u32, addend: u8) {
+    let mut carry = addend as u32;
+    for byte in value.iter_mut().rev() {
+        let product = *byte as u32 * factor + carry;
+        *byte = product as u8;
+        carry = product >> 8;
+    }
+}
+
+/// Compiled pattern expression
+pub enum Node {
+    /// Empty pattern, matches every address
+    Always,
+    Term(Term),
+    /// Any word of a dictionary at its configured position
+    Words(WordList),
+    /// Literal prefix or anywhere term within an edit distance
+    Fuzzy(FuzzyTerm),
+    /// Raw key bytes under a mask, checked before any encoding
+    Bytes(ByteMask),
+    /// Raw key within a sort-order interval
+    Range(KeyRange),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+}
+
+impl Node {
+    fn probability(&self, format: &dyn AddressFormat) -> f64 {
+        match self {
+            Node::Always => 1.0,
+            Node::Term(term) => term.probability(format),
+            Node::Words(words) => words.probability(),
+            Node::Fuzzy(term) => term.probability(),
+            Node::Bytes(bytes) => bytes.probability(),
+            Node::Range(range) => range.probability(),
+            Node::And(a, b) => a.probability(format) * b.probability(format),
+            Node::Or(a, b) => {
+                let (a, b) = (a.probability(format), b.probability(format));
+                a + b - a * b
+            }
+            Node::Not(node) => 1.0 - node.probability(format),
+        }
+    }
+
+    /// Collects the parts of the address matched by satisfied terms, returning
+    /// whether this node matched
+    fn spans(&self, address: &str, spans: &mut Vec<Range<usize>>) -> bool {
+        match self {
+            // Byte constraints are decided on the key and cover no characters
+            Node::Always | Node::Bytes(_) | Node::Range(_) => true,
+            Node::Term(term) => term
+                .find(address.as_bytes())
+                .map(|range| spans.push(range))
+                .is_some(),
+            Node::Words(words) => words
+                .find_span(address)
+                .map(|(_, range)| spans.push(range))
+                .is_some(),
+            Node::Fuzzy(term) => term
+                .find(address)
+                .map(|found| spans.push(found.range))
+                .is_some(),
+            Node::And(a, b) => a.spans(address, spans) & b.spans(address, spans),
+            Node::Or(a, b) => a.spans(address, spans) | b.spans(address, spans),
+            // A negated term matches by being absent, so it covers nothing
+            Node::Not(node) => !node.spans(address, &mut Vec::new()),
+        }
+    }
+
+    /// Fewest characters a match covers, for estimating the rest of the address
+    fn matched_len(&self) -> usize {
+        match self {
+            Node::Always | Node::Words(_) | Node::Bytes(_) | Node::Range(_) | Node::Not(_) => 0,
+            Node::Term(term) => term.sets.len(),
+            Node::Fuzzy(term) => term.target_len(),
+            Node::And(a, b) => a.matched_len() + b.matched_len(),
+            Node::Or(a, b) => a.matched_len().min(b.matched_len()),
+        }
+    }
+
+    /// Replaces literal prefix and anywhere terms with fuzzy ones, returning how many
+    fn make_fuzzy(
+        self,
+        max_distance: f64,
+        metric: EditMetric,
+        costs: EditCosts,
+        case_sensitive: bool,
+    ) -> (Node, usize) {
+        let fuzzy = |a: Node, b: Node, join: fn(Box<Node>, Box<Node>) -> Node| {
+            let (a, a_count) = a.make_fuzzy(max_distance, metric, costs, case_sensitive);
+            let (b, b_count) = b.make_fuzzy(max_distance, metric, costs, case_sensitive);
+            (join(Box::new(a), Box::new(b)), a_count + b_count)
+        };
+        match self {
+            Node::Term(Term {
+                anchor: anchor @ (Anchor::Prefix | Anchor::Anywhere),
+                literal: Some(literal),
+                ..
+            }) => (
+                Node::Fuzzy(FuzzyTerm::new(
+                    &literal,
+                    anchor == Anchor::Anywhere,
+                    max_distance,
+                    metric,
+                    costs,
+                    case_sensitive,
+                )),
+                1,
+            ),
+            Node::And(a, b) => fuzzy(*a, *b, Node::And),
+            Node::Or(a, b) => fuzzy(*a, *b, Node::Or),
+            // Negated terms stay exact, a fuzzy NOT would reject far more than intended
+            node => (node, 0),
+        }
+    }
+
+    /// Literal text of a prefix or suffix term with the given anchor
+    fn literal(&self, anchor: Anchor) -> Option<&str> {
+        match self {
+            Node::Term(term) if term.anchor == anchor => term.literal.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Address pattern such as `^S[oO]l AND (x$ OR @5:??z) AND NOT 111`.
+///
+/// Terms are `^prefix`, `suffix$`, `@offset:chars` or `chars` (anywhere). Within a term `?`
+/// matches any character and `[...]` is a class like `[0-9]`, `[a-z]` or `[^xyz]`. Plain
+/// characters follow the global case sensitivity while classes always match exactly,
+/// which gives per-character case masks. Terms combine with `AND`, `OR`, `NOT` and parentheses.
+/// Terms match the chain's address after its fixed prefix, e.g. the hex after `0x`.
+pub struct Pattern {
+    root: Node,
+    source: String,
+    label: String,
+    case_sensitive: bool,
+    filters: Option<Filters>,
+    chain: Chain,
+}
+
+impl Pattern {
+    pub fn parse(expr: &str, case_sensitive: bool) -> Result<Self> {
+        Self::parse_for(expr, case_sensitive, Chain::Solana)
+    }
+
+    /// Parses against another chain's address alphabet
+    pub fn parse_for(expr: &str, case_sensitive: bool, chain: Chain) -> Result<Self> {
+        let format = chain.format();
+        let tokens = tokenize(expr);
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            case_sensitive,
+            format,
+        };
+        let root = if tokens.is_empty() {
+            Node::Always
+        } else {
+            let root = parser.parse_or()?;
+            if parser.position < tokens.len() {
+                bail!("Unexpected {} in pattern", tokens[parser.position]);
+            }
+            root
+        };
+
+        Ok(Pattern {
+            root,
+            source: expr.trim().to_string(),
+            label: expr.trim().to_string(),
+            case_sensitive,
+            filters: None,
+            chain,
+        })
+    }
+
+    /// Rebuilds a pattern from the spec recorded in a result, or None if the spec only
+    /// summarizes some of its parts
+    pub fn from_spec(spec: &str, case_sensitive: bool, chain: Chain) -> Result<Option<Self>> {
+        if SUMMARIZED_SPEC_PARTS.iter().any(|part| spec.contains(part)) {
+            return Ok(None);
+        }
+        let expr = if spec == ANY_ADDRESS { "" } else { spec };
+        Self::parse_for(expr, case_sensitive, chain).map(Some)
+    }
+
+    /// Equivalent of the `--starts-with`/`--ends-with` literals
+    pub fn from_affixes(
+        starts_with: Option<String>,
+        ends_with: Option<String>,
+        case_sensitive: bool,
+    ) -> Result<Self> {
+        Self::affixes_for(starts_with, ends_with, case_sensitive, Chain::Solana)
+    }
+
+    fn affixes_for(
+        starts_with: Option<String>,
+        ends_with: Option<String>,
+        case_sensitive: bool,
+        chain: Chain,
+    ) -> Result<Self> {
+        let source = [
+            starts_with.as_ref().map(|prefix| format!("^{}", prefix)),
+            ends_with.as_ref().map(|suffix| format!("{}$", suffix)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+        let mut pattern = Self::parse_for(&source, case_sensitive, chain)?;
+        pattern.label = starts_with
+            .or(ends_with)
+            .unwrap_or_else(|| "random".to_string());
+        Ok(pattern)
+    }
+
+    /// Builds the pattern from the search command's `--pattern` or affix options
+    pub fn from_args(
+        pattern: Option<String>,
+        starts_with: Option<String>,
+        ends_with: Option<String>,
+        case_sensitive: bool,
+        chain: Chain,
+    ) -> Result<Self> {
+        match pattern {
+            Some(expr) => Self::parse_for(&expr, case_sensitive, chain),
+            None => Self::affixes_for(starts_with, ends_with, case_sensitive, chain),
+        }
+    }
+
+    /// Additionally requires one of the dictionary words
+    pub fn with_wordlist(mut self, words: WordList) -> Self {
+        let description = format!("wordlist ({} words, {:?})", words.len(), words.position());
+        self.source = if self.source.is_empty() {
+            description
+        } else {
+            format!("({}) AND {}", self.source, description)
+        };
+        self.root = match self.root {
+            Node::Always => Node::Words(words),
+            root => Node::And(Box::new(root), Box::new(Node::Words(words))),
+        };
+        if self.label == "random" {
+            self.label = "wordlist".to_string();
+        }
+        self
+    }
+
+    /// Lets literal prefix and anywhere terms match within `max_distance` edits
+    pub fn with_max_distance(
+        mut self,
+        max_distance: f64,
+        metric: EditMetric,
+        costs: EditCosts,
+    ) -> Result<Self> {
+        let (root, count) = self
+            .root
+            .make_fuzzy(max_distance, metric, costs, self.case_sensitive);
+        self.root = root;
+        if count == 0 {
+            bail!("--max-distance needs a plain literal prefix or anywhere term");
+        }
+        self.source = format!(
+            "{} (within {} {:?} edits)",
+            self.source, max_distance, metric
+        );
+        Ok(self)
+    }
+
+    /// Additionally requires the raw key bytes to match, checked first as it needs no encoding
+    pub fn with_bytes(self, bytes: ByteMask) -> Self {
+        self.and_first(bytes.to_string(), Node::Bytes(bytes), "bytes")
+    }
+
+    /// Additionally requires the raw key to sort inside `range`, checked first like bytes
+    pub fn with_key_range(self, range: KeyRange) -> Self {
+        self.and_first(range.to_string(), Node::Range(range), "range")
+    }
+
+    /// Puts a key-level node in front of the expression so misses skip the encoding
+    fn and_first(mut self, description: String, node: Node, label: &str) -> Self {
+        self.source = if self.source.is_empty() {
+            description
+        } else {
+            format!("{} AND ({})", description, self.source)
+        };
+        self.root = match self.root {
+            Node::Always => node,
+            root => Node::And(Box::new(node), Box::new(root)),
+        };
+        if self.label == "random" {
+            self.label = label.to_string();
+        }
+        self
+    }
+
+    /// Raw byte constraint added by `with_bytes`, or an empty mask
+    pub fn byte_mask(&self) -> ByteMask {
+        match &self.root {
+            Node::Bytes(bytes) => *bytes,
+            Node::And(a, _) => match **a {
+                Node::Bytes(bytes) => bytes,
+                _ => ByteMask::default(),
+            },
+            _ => ByteMask::default(),
+        }
+    }
+
+    /// Rejects matching addresses that fail the structural filters
+    pub fn with_filters(mut self, filters: Filters) -> Self {
+        if !filters.is_empty() {
+            self.filters = Some(filters);
+        }
+        self
+    }
+
+    pub fn filters(&self) -> Option<&Filters> {
+        self.filters.as_ref()
+    }
+
+    /// Ranges of a matching address covered by the pattern's terms and words
+    pub fn matched_spans(&self, address: &str) -> Vec<Range<usize>> {
+        let mut spans = Vec::new();
+        self.root.spans(address, &mut spans);
+        spans
+    }
+
+    /// Closest text of the address matching a fuzzy term, for reporting
+    pub fn fuzzy_match(&self, address: &str) -> Option<FuzzyMatch> {
+        fn find(node: &Node, address: &str) -> Option<FuzzyMatch> {
+            match node {
+                Node::Fuzzy(term) => term.find(address),
+                Node::And(a, b) | Node::Or(a, b) => find(a, address).or_else(|| find(b, address)),
+                _ => None,
+            }
+        }
+        find(&self.root, address)
+    }
+
+    /// Dictionary word found in a matching address, for reporting
+    pub fn matched_word(&self, address: &str) -> Option<String> {
+        fn find<'a>(node: &'a Node, address: &str) -> Option<&'a str> {
+            match node {
+                Node::Words(words) => words.find(address),
+                Node::And(a, b) | Node::Or(a, b) => find(a, address).or_else(|| find(b, address)),
+                _ => None,
+            }
+        }
+        find(&self.root, address).map(str::to_string)
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// Stored as `pattern_matched` in results
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    pub fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    /// Address format the terms are matched against
+    pub fn format(&self) -> &'static dyn AddressFormat {
+        self.chain.format()
+    }
+
+    /// Display address on the pattern's chain, or None for Solana where it is the pubkey
+    pub fn chain_address(&self, key: &[u8; 32]) -> Option<String> {
+        (self.chain != Chain::Solana).then(|| self.format().address(key))
+    }
+
+    /// Chance that a uniformly random address matches, treating terms as independent
+    pub fn probability(&self) -> f64 {
+        let filtered = self
+            .filters
+            .as_ref()
+            .map_or(1.0, |filters| filters.probability(self.root.matched_len()));
+        self.root.probability(self.format()) * filtered
+    }
+
+    /// Prefix and suffix literals for kernels that only understand `starts_with`/`ends_with`,
+    /// next to the `byte_mask` they also check
+    pub fn simple_affixes(&self) -> Option<(String, String)> {
+        let root = match &self.root {
+            Node::Bytes(_) => &Node::Always,
+            Node::And(a, b) if matches!(**a, Node::Bytes(_)) => &**b,
+            root => root,
+        };
+        match root {
+            Node::Always => Some((String::new(), String::new())),
+            Node::And(a, b) => {
+                let (prefix, suffix) = match (a.literal(Anchor::Prefix), b.literal(Anchor::Suffix))
+                {
+                    (Some(prefix), Some(suffix)) => (prefix, suffix),
+                    _ => (b.literal(Anchor::Prefix)?, a.literal(Anchor::Suffix)?),
+                };
+                Some((prefix.to_string(), suffix.to_string()))
+            }
+            node => match (node.literal(Anchor::Prefix), node.literal(Anchor::Suffix)) {
+                (Some(prefix), _) => Some((prefix.to_string(), String::new())),
+                (_, Some(suffix)) => Some((String::new(), suffix.to_string())),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Spec of a pattern that matches every address
+const ANY_ADDRESS: &str = "any address";
+
+/// Parts of a spec that summarize word lists, edit distances, raw key bytes or ranges
+/// and filters, which cannot be rebuilt from the text
+const SUMMARIZED_SPEC_PARTS: [&str; 4] = [", filtered by ", "wordlist (", " edits)", " bits)"];
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.source.is_empty() {
+            write!(f, "{}", ANY_ADDRESS)?;
+        } else {
+            write!(f, "{}", self.source)?;
+        }
+        match &self.filters {
+            Some(filters) => write!(f, ", filtered by {}", filters),
+            None => Ok(()),
+        }
+    }
+}
+
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Term(term) => write!(f, "'{}'", term),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Term(word),
+                });
+            }
+        }
+    }
+    tokens
+}
+
+/// Recursive descent with the usual precedence: NOT, then AND, then OR
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    case_sensitive: bool,
+    format: &'static dyn AddressFormat,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn peek_is(&self, expected: fn(&Token) -> bool) -> bool {
+        self.tokens.get(self.position).is_some_and(expected)
+    }
+
+    fn parse_or(&mut self) -> Result<Node> {
+        let mut node = self.parse_and()?;
+        while self.peek_is(|token| matches!(token, Token::Or)) {
+            self.position += 1;
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node> {
+        let mut node = self.parse_unary()?;
+        while self.peek_is(|token| matches!(token, Token::And)) {
+            self.position += 1;
+            node = Node::And(Box::new(node), Box::new(self.parse_unary()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node> {
+        let (case_sensitive, format) = (self.case_sensitive, self.format);
+        match self.next() {
+            Some(Token::Not) => Ok(Node::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let node = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(node),
+                    _ => bail!("Missing ')' in pattern"),
+                }
+            }
+            Some(Token::Term(text)) => Ok(Node::Term(parse_term(text, case_sensitive, format)?)),
+            Some(token) => bail!("Unexpected {} in pattern", token),
+            None => bail!("Pattern ends where a term was expected"),
+        }
+    }
+}
+
+fn parse_term(text: &str, case_sensitive: bool, format: &dyn AddressFormat) -> Result<Term> {
+    let alphabet = format.alphabet();
+    let (anchor, body) = if let Some(rest) = text.strip_prefix('@') {
+        let (offset, body) = rest
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Offset term '{}' must be @<offset>:<chars>", text))?;
+        (Anchor::Offset(offset.parse()?), body)
+    } else if let Some(body) = text.strip_prefix('^') {
+        if body.ends_with('$') {
+            bail!("Use AND to combine a prefix and a suffix in '{}'", text);
+        }
+        (Anchor::Prefix, body)
+    } else if let Some(body) = text.strip_suffix('$') {
+        (Anchor::Suffix, body)
+    } else {
+        (Anchor::Anywhere, text)
+    };
+
+    let mut sets = Vec::new();
+    let mut chars = body.bytes();
+    while let Some(c) = chars.next() {
+        let set = match c {
+            b'?' => CharSet::of(alphabet),
+            b'[' => {
+                let mut class = Vec::new();
+                loop {
+                    match chars.next() {
+                        Some(b']') => break,
+                        Some(c) => class.push(c),
+                        None => bail!("Missing ']' in '{}'", text),
+                    }
+                }
+                parse_class(&class, alphabet)
+                    .ok_or_else(|| anyhow!("Invalid class in '{}'", text))?
+            }
+            c if case_sensitive => CharSet::from_chars([c], alphabet),
+            c => CharSet::from_chars([c.to_ascii_lowercase(), c.to_ascii_uppercase()], alphabet),
+        };
+        if set.len() == 0 {
+            bail!(
+                "'{}' in '{}' is not in the {} address alphabet",
+                c as char,
+                text,
+                format.name()
+            );
+        }
+        sets.push(set);
+    }
+    if sets.is_empty() {
+        bail!("Empty term '{}' in pattern", text);
+    }
+    if let Anchor::Offset(offset) = anchor {
+        if offset + sets.len() > format.max_len() {
+            bail!(
+                "'{}' extends past the end of a {}-character address",
+                text,
+                format.max_len()
+            );
+        }
+    }
+
+    let mut term = Term {
+        anchor,
+        sets,
+        literal: (!body.contains(['?', '['])).then(|| body.to_string()),
+        fast_path: None,
+    };
+    if format.is_solana() {
+        term.compile();
+    }
+    Ok(term)
+}
+
+/// Contents of `[...]`: characters and `a-z` ranges, negated by a leading `^`
+fn parse_class(class: &[u8], alphabet: &[u8]) -> Option<CharSet> {
+    let (negated, class) = match class.split_first() {
+        Some((b'^', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut chars = Vec::new();
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            if class[i] > class[i + 2] {
+                return None;
+            }
+            chars.extend(class[i]..=class[i + 2]);
+            i += 3;
+        } else {
+            chars.push(class[i]);
+            i += 1;
+        }
+    }
+    let set = CharSet::from_chars(chars, alphabet);
+    Some(if negated {
+        CharSet::of(alphabet).difference(&set)
+    } else {
+        set
+    })
+}
@@ -0,0 +1,295 @@
+use anyhow::{anyhow, bail, Result};
+use rand::Rng;
+use rayon::prelude::*;
+use solana_sdk::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::keystore::KeystoreHeader;
+use crate::pattern::Pattern;
+use crate::seed_address::SEED_ALPHABET;
+use crate::utils::{
+    check_pattern_match, display_results, estimate_attempts_needed, format_attempts, new_run_id,
+    open_run_output, save_hit, Provenance, ResultsLock, VanityResult,
+};
+use crate::verify::confirm_result;
+
+/// A fixed seed component given as `<type>:<value>`
+#[derive(Clone)]
+pub enum SeedComponent {
+    Fixed {
+        bytes: Vec<u8>,
+        spec: String,
+    },
+    /// Placeholder for the variable component
+    Variable,
+}
+
+impl FromStr for SeedComponent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "var" {
+            return Ok(SeedComponent::Variable);
+        }
+        let (kind, value) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Seed '{}' must be <type>:<value> or 'var'", s))?;
+        let bytes = match kind {
+            "str" => value.as_bytes().to_vec(),
+            "hex" => hex::decode(value)?,
+            "pubkey" => Pubkey::from_str(value)?.to_bytes().to_vec(),
+            "u8" => vec![value.parse::<u8>()?],
+            "u16" => value.parse::<u16>()?.to_le_bytes().to_vec(),
+            "u32" => value.parse::<u32>()?.to_le_bytes().to_vec(),
+            "u64" => value.parse::<u64>()?.to_le_bytes().to_vec(),
+            _ => bail!(
+                "Unknown seed type '{}' (use str, hex, pubkey, u8, u16, u32 or u64)",
+                kind
+            ),
+        };
+        if bytes.len() > MAX_SEED_LEN {
+            bail!("Seed '{}' is longer than {} bytes", s, MAX_SEED_LEN);
+        }
+        Ok(SeedComponent::Fixed {
+            bytes,
+            spec: s.to_string(),
+        })
+    }
+}
+
+/// The component that is ground: a counter, random bytes or a random string
+#[derive(Clone, Copy)]
+pub enum VariableSeed {
+    /// u64 counter, little-endian like `u64::to_le_bytes` in Anchor seeds
+    U64,
+    Bytes(usize),
+    String(usize),
+}
+
+impl FromStr for VariableSeed {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, len) = match s.split_once(':') {
+            Some((kind, len)) => (kind, Some(len.parse::<usize>()?)),
+            None => (s, None),
+        };
+        let variable = match (kind, len) {
+            ("u64", None) => VariableSeed::U64,
+            ("bytes", Some(len)) => VariableSeed::Bytes(len),
+            ("string", Some(len)) => VariableSeed::String(len),
+            _ => bail!("Variable seed must be u64, bytes:<len> or string:<len>"),
+        };
+        if let VariableSeed::Bytes(len) | VariableSeed::String(len) = variable {
+            if len == 0 || len > MAX_SEED_LEN {
+                bail!(
+                    "Variable seed length must be between 1 and {}",
+                    MAX_SEED_LEN
+                );
+            }
+        }
+        Ok(variable)
+    }
+}
+
+impl VariableSeed {
+    fn generate(&self, index: u64) -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        match self {
+            VariableSeed::U64 => index.to_le_bytes().to_vec(),
+            VariableSeed::Bytes(len) => (0..*len).map(|_| rng.gen()).collect(),
+            VariableSeed::String(len) => (0..*len)
+                .map(|_| SEED_ALPHABET[rng.gen_range(0..SEED_ALPHABET.len())])
+                .collect(),
+        }
+    }
+
+    /// Encodes a generated value so it can be pasted back as a `--seed`
+    fn describe(&self, value: &[u8]) -> String {
+        match self {
+            VariableSeed::U64 => format!(
+                "u64:{}",
+                u64::from_le_bytes(value.try_into().expect("u64 seed is 8 bytes"))
+            ),
+            VariableSeed::Bytes(_) => format!("hex:{}", hex::encode(value)),
+            VariableSeed::String(_) => format!("str:{}", String::from_utf8_lossy(value)),
+        }
+    }
+}
+
+/// Searches for program-derived addresses whose address matches the patterns
+pub struct PdaGenerator {
+    program_id: Pubkey,
+    seeds: Vec<SeedComponent>,
+    variable: VariableSeed,
+    bump: Option<u8>,
+    pattern: Pattern,
+    count: usize,
+    output_path: String,
+    results: Vec<VanityResult>,
+    /// Header of the output if it is an encrypted keystore, kept for every save
+    keystore: Option<KeystoreHeader>,
+    _lock: ResultsLock,
+}
+
+impl PdaGenerator {
+    pub fn new(
+        program_id: Pubkey,
+        mut seeds: Vec<SeedComponent>,
+        variable: VariableSeed,
+        bump: Option<u8>,
+        pattern: Pattern,
+        count: usize,
+        output_path: String,
+    ) -> Result<Self> {
+        match seeds
+            .iter()
+            .filter(|seed| matches!(seed, SeedComponent::Variable))
+            .count()
+        {
+            0 => seeds.push(SeedComponent::Variable),
+            1 => {}
+            _ => bail!("Only one 'var' seed component is allowed"),
+        }
+        // The bump seed always takes one of the slots
+        if seeds.len() + 1 > MAX_SEEDS {
+            bail!("At most {} seed components are allowed", MAX_SEEDS - 1);
+        }
+
+        let lock = ResultsLock::acquire(&output_path)?;
+        let (results, keystore) = open_run_output(&output_path)?;
+
+        Ok(PdaGenerator {
+            program_id,
+            seeds,
+            variable,
+            bump,
+            pattern,
+            count,
+            output_path,
+            results,
+            keystore,
+            _lock: lock,
+        })
+    }
+
+    /// Derives the address for one variable value, rejecting on-curve hashes like the runtime
+    pub fn derive(&self, variable: &[u8]) -> Option<(Pubkey, u8)> {
+        let seeds: Vec<&[u8]> = self
+            .seeds
+            .iter()
+            .map(|seed| match seed {
+                SeedComponent::Fixed { bytes, .. } => bytes.as_slice(),
+                SeedComponent::Variable => variable,
+            })
+            .collect();
+
+        match self.bump {
+            Some(bump) => {
+                let mut seeds = seeds;
+                let bump_seed = [bump];
+                seeds.push(&bump_seed);
+                Pubkey::create_program_address(&seeds, &self.program_id)
+                    .ok()
+                    .map(|address| (address, bump))
+            }
+            None => Pubkey::try_find_program_address(&seeds, &self.program_id),
+        }
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        println!("🚀 Starting program-derived address vanity search");
+        println!("Program ID: {}", self.program_id);
+        match self.bump {
+            Some(bump) => println!("Bump: {}", bump),
+            None => println!("Bump: canonical (find_program_address)"),
+        }
+        println!("Pattern: {}", self.pattern);
+        println!("Target count: {}", self.count);
+        println!("Case sensitive: {}", self.pattern.case_sensitive());
+        println!(
+            "Estimated attempts needed: {}",
+            format_attempts(estimate_attempts_needed(&self.pattern))
+        );
+
+        let provenance =
+            Provenance::new(&new_run_id(), "cpu", "pda", self.pattern.case_sensitive());
+        let batch_size = 1 << 18;
+        let start_time = Instant::now();
+        let mut next_index = 0u64;
+        let mut found_count = 0;
+
+        while found_count < self.count {
+            let hits: Vec<(Vec<u8>, Pubkey, u8, u64)> = (next_index
+                ..next_index + batch_size as u64)
+                .into_par_iter()
+                .filter_map(|index| {
+                    let variable = self.variable.generate(index);
+                    let (address, bump) = self.derive(&variable)?;
+                    check_pattern_match(&address, &self.pattern)
+                        .then_some((variable, address, bump, index))
+                })
+                .collect();
+
+            for (variable, address, bump, index) in hits {
+                let seeds = self
+                    .seeds
+                    .iter()
+                    .map(|seed| match seed {
+                        SeedComponent::Fixed { spec, .. } => spec.clone(),
+                        SeedComponent::Variable => self.variable.describe(&variable),
+                    })
+                    .collect();
+
+                let fuzzy_match = self.pattern.fuzzy_match(&address.to_string());
+                let result = VanityResult {
+                    public_key: address.to_string(),
+                    pattern_matched: self.pattern.label().to_string(),
+                    matched_word: self.pattern.matched_word(&address.to_string()),
+                    fuzzy_text: fuzzy_match.as_ref().map(|matched| matched.text.clone()),
+                    edit_distance: fuzzy_match.map(|matched| matched.distance),
+                    attempts: index + 1,
+                    found_at: chrono::Utc::now(),
+                    program_id: Some(self.program_id.to_string()),
+                    seeds: Some(seeds),
+                    bump: Some(bump),
+                    pattern: Some(self.pattern.to_string()),
+                    match_positions: Some(self.pattern.matched_spans(&address.to_string())),
+                    provenance: Some(provenance.clone()),
+                    ..Default::default()
+                };
+                if !confirm_result(&result, None) {
+                    continue;
+                }
+                if !save_hit(
+                    &mut self.results,
+                    result,
+                    &self.output_path,
+                    self.keystore.as_ref(),
+                )? {
+                    continue;
+                }
+
+                found_count += 1;
+                if found_count >= self.count {
+                    break;
+                }
+            }
+
+            next_index += batch_size as u64;
+
+            let timestamp = chrono::Utc::now().format("%H:%M:%S");
+            let speed_khps = next_index as f64 / start_time.elapsed().as_secs_f64() / 1e3;
+            println!("[{}] Speed: {:.2} KH/s", timestamp, speed_khps);
+        }
+
+        println!("\n🎉 Search completed!");
+        println!("Total time: {:.2}s", start_time.elapsed().as_secs_f64());
+        println!("Total attempts: {}", format_attempts(next_index));
+
+        // Seed and program-derived addresses have no secrets to redact
+        display_results(&self.results, false);
+        Ok(())
+    }
+}
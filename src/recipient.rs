@@ -0,0 +1,62 @@
+use age::armor::{ArmoredWriter, Format};
+use age::{x25519, Encryptor};
+use anyhow::{anyhow, bail, Result};
+use std::fs;
+use std::io::Write;
+
+use crate::keystore::SecretFields;
+use crate::utils::VanityResult;
+
+/// age X25519 public keys that found secrets are encrypted to as soon as they are found,
+/// so the host running the search never holds them at rest
+pub struct AgeRecipients(Vec<x25519::Recipient>);
+
+impl AgeRecipients {
+    /// An `age1...` public key, or a recipients file with one per line
+    /// (blank lines and `#` comments are skipped, as in `age -R`)
+    pub fn parse(spec: &str) -> Result<Self> {
+        if spec.starts_with("age1") {
+            return Ok(AgeRecipients(vec![parse_recipient(spec)?]));
+        }
+        let content = fs::read_to_string(spec)
+            .map_err(|e| anyhow!("Cannot read recipients file {}: {}", spec, e))?;
+        let recipients = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(parse_recipient)
+            .collect::<Result<Vec<_>>>()?;
+        if recipients.is_empty() {
+            bail!("No age recipients in {}", spec);
+        }
+        Ok(AgeRecipients(recipients))
+    }
+
+    /// Replaces the secrets of a result with an ASCII-armored age file of their JSON,
+    /// wiping the plaintext; secretless results are kept
+    pub fn seal(&self, result: &mut VanityResult) -> Result<()> {
+        let Some(fields) = SecretFields::take(result) else {
+            return Ok(());
+        };
+        let recipients = self
+            .0
+            .iter()
+            .map(|recipient| Box::new(recipient.clone()) as Box<dyn age::Recipient>)
+            .collect();
+
+        let mut armored = Vec::new();
+        let mut writer = Encryptor::with_recipients(recipients).wrap_output(
+            ArmoredWriter::wrap_output(&mut armored, Format::AsciiArmor)?,
+        )?;
+        writer.write_all(&fields.to_json()?)?;
+        writer.finish()?.finish()?;
+
+        result.age_secret = Some(String::from_utf8(armored)?);
+        Ok(())
+    }
+}
+
+fn parse_recipient(key: &str) -> Result<x25519::Recipient> {
+    key.parse()
+        .map_err(|e| anyhow!("Invalid age recipient {}: {}", key, e))
+}
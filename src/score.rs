@@ -0,0 +1,263 @@
+use anyhow::{anyhow, bail, Result};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::utils::VanityResult;
+use crate::wordlist::{WordCase, WordList, WordPosition};
+
+/// One ingredient of a best-effort score, computed from the encoded address
+pub trait Scorer: Send + Sync {
+    fn score(&self, address: &str) -> f64;
+}
+
+/// Number of leading characters equal to the target, ignoring case
+pub struct PrefixScorer {
+    target: String,
+}
+
+impl Scorer for PrefixScorer {
+    fn score(&self, address: &str) -> f64 {
+        address
+            .bytes()
+            .zip(self.target.bytes())
+            .take_while(|(a, t)| a.eq_ignore_ascii_case(t))
+            .count() as f64
+    }
+}
+
+/// Characters of the matching prefix whose case is also exact
+pub struct CaseScorer {
+    target: String,
+}
+
+impl Scorer for CaseScorer {
+    fn score(&self, address: &str) -> f64 {
+        address
+            .bytes()
+            .zip(self.target.bytes())
+            .take_while(|(a, t)| a.eq_ignore_ascii_case(t))
+            .filter(|(a, t)| a == t)
+            .count() as f64
+    }
+}
+
+/// Length of the longest run of one repeated character, beyond the first
+pub struct RunScorer;
+
+impl Scorer for RunScorer {
+    fn score(&self, address: &str) -> f64 {
+        let bytes = address.as_bytes();
+        let mut longest = 0;
+        let mut current = 0;
+        for i in 1..bytes.len() {
+            current = if bytes[i] == bytes[i - 1] {
+                current + 1
+            } else {
+                0
+            };
+            longest = longest.max(current);
+        }
+        longest as f64
+    }
+}
+
+/// Length of the longest dictionary word anywhere in the address
+pub struct WordScorer {
+    words: WordList,
+}
+
+impl Scorer for WordScorer {
+    fn score(&self, address: &str) -> f64 {
+        self.words.longest_match(address) as f64
+    }
+}
+
+/// Weighted sum of several scorers, parsed from `prefix,case=0.5,runs,words:dict.txt=2`
+pub struct CompositeScorer {
+    components: Vec<(f64, Box<dyn Scorer>)>,
+    description: String,
+}
+
+impl CompositeScorer {
+    pub fn parse(spec: &str, target: Option<&str>) -> Result<Self> {
+        let mut components: Vec<(f64, Box<dyn Scorer>)> = Vec::new();
+        for component in spec.split(',').map(str::trim) {
+            let (name, weight) = match component.rsplit_once('=') {
+                Some((name, weight)) => (name, weight.parse::<f64>()?),
+                None => (component, 1.0),
+            };
+            let need_target = || {
+                target
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow!("Score component '{}' needs --target", name))
+            };
+            let scorer: Box<dyn Scorer> = match name.split_once(':') {
+                None if name == "prefix" => Box::new(PrefixScorer {
+                    target: need_target()?,
+                }),
+                None if name == "case" => Box::new(CaseScorer {
+                    target: need_target()?,
+                }),
+                None if name == "runs" => Box::new(RunScorer),
+                Some(("words", path)) => Box::new(WordScorer {
+                    words: WordList::load(path, 3, WordPosition::Anywhere, WordCase::Any)?,
+                }),
+                _ => bail!(
+                    "Unknown score component '{}' (use prefix, case, runs or words:<file>)",
+                    name
+                ),
+            };
+            components.push((weight, scorer));
+        }
+
+        Ok(CompositeScorer {
+            components,
+            description: spec.to_string(),
+        })
+    }
+
+    pub fn score(&self, address: &str) -> f64 {
+        self.components
+            .iter()
+            .map(|(weight, scorer)| weight * scorer.score(address))
+            .sum()
+    }
+}
+
+impl fmt::Display for CompositeScorer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+/// Time and attempt limits of a best-effort search; unlimited if both are None
+#[derive(Clone, Copy)]
+pub struct Budget {
+    pub max_time: Option<Duration>,
+    pub max_attempts: Option<u64>,
+}
+
+impl Budget {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_time.is_none() && self.max_attempts.is_none()
+    }
+
+    pub fn exhausted(&self, start_time: Instant, attempts: u64) -> bool {
+        self.max_time
+            .is_some_and(|max_time| start_time.elapsed() >= max_time)
+            || self
+                .max_attempts
+                .is_some_and(|max_attempts| attempts >= max_attempts)
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.max_time, self.max_attempts) {
+            (None, None) => write!(f, "no budget"),
+            (Some(time), None) => write!(f, "max time {}s", time.as_secs()),
+            (None, Some(attempts)) => write!(f, "max attempts {}", attempts),
+            (Some(time), Some(attempts)) => {
+                write!(f, "max time {}s, max attempts {}", time.as_secs(), attempts)
+            }
+        }
+    }
+}
+
+/// Best-effort search settings: keep the `top` best addresses until the budget runs out
+pub struct ScoreConfig {
+    pub scorer: CompositeScorer,
+    pub top: usize,
+    pub budget: Budget,
+}
+
+impl ScoreConfig {
+    pub fn new(scorer: CompositeScorer, top: usize, budget: Budget) -> Result<Self> {
+        if budget.is_unlimited() {
+            bail!("Score mode needs --max-time or --max-attempts");
+        }
+        if top == 0 {
+            bail!("--top must keep at least one address");
+        }
+        Ok(ScoreConfig {
+            scorer,
+            top,
+            budget,
+        })
+    }
+}
+
+struct Scored(VanityResult);
+
+impl Scored {
+    fn score(&self) -> f64 {
+        self.0.score.unwrap_or(f64::MIN)
+    }
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score().total_cmp(&other.score())
+    }
+}
+
+/// Min-heap holding the `capacity` highest-scoring results seen so far
+pub struct TopN {
+    heap: BinaryHeap<Reverse<Scored>>,
+    capacity: usize,
+}
+
+impl TopN {
+    pub fn new(capacity: usize) -> Self {
+        TopN {
+            heap: BinaryHeap::with_capacity(capacity + 1),
+            capacity,
+        }
+    }
+
+    /// Score a candidate must beat to enter the list
+    pub fn threshold(&self) -> f64 {
+        if self.heap.len() < self.capacity {
+            f64::MIN
+        } else {
+            self.heap
+                .peek()
+                .map_or(f64::MIN, |Reverse(lowest)| lowest.score())
+        }
+    }
+
+    /// Returns true if the result made it into the list
+    pub fn offer(&mut self, result: VanityResult) -> bool {
+        if result.score.unwrap_or(f64::MIN) <= self.threshold() {
+            return false;
+        }
+        self.heap.push(Reverse(Scored(result)));
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+        true
+    }
+
+    /// Results from best to worst
+    pub fn sorted(&self) -> Vec<VanityResult> {
+        let mut results: Vec<&Scored> = self.heap.iter().map(|Reverse(scored)| scored).collect();
+        results.sort_by(|a, b| b.cmp(a));
+        results.into_iter().map(|scored| scored.0.clone()).collect()
+    }
+}
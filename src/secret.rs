@@ -0,0 +1,75 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Shown in place of a secret on the console unless `--show-secrets` is given
+pub const REDACTED: &str = "(hidden, pass --show-secrets to print)";
+
+/// Private key, mnemonic or passphrase text that is wiped when dropped, kept out of swap
+/// where the OS allows it, and only readable through `expose`, so it is never printed by accident
+#[derive(Default, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Self {
+        let secret = Secret(value);
+        secret.lock(true);
+        secret
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Locks or unlocks the pages of the buffer. Failures, such as a low RLIMIT_MEMLOCK, only
+    /// lose the swap protection; so does unlocking a page shared with another secret.
+    fn lock(&self, locked: bool) {
+        #[cfg(unix)]
+        if self.0.capacity() > 0 {
+            let address = self.0.as_ptr() as *const libc::c_void;
+            // SAFETY: the range is this string's own allocation
+            unsafe {
+                if locked {
+                    libc::mlock(address, self.0.capacity());
+                } else {
+                    libc::munlock(address, self.0.capacity());
+                }
+            }
+        }
+    }
+}
+
+impl Clone for Secret {
+    fn clone(&self) -> Self {
+        Secret::new(self.0.clone())
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        self.lock(false);
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret::new)
+    }
+}
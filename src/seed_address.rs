@@ -0,0 +1,227 @@
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use rayon::prelude::*;
+use solana_sdk::pubkey::Pubkey;
+use std::time::Instant;
+
+use crate::keystore::KeystoreHeader;
+use crate::opencl::OpenCLManager;
+use crate::pattern::Pattern;
+use crate::utils::{
+    check_pattern_match, check_primary_match, display_results, estimate_attempts_needed,
+    format_attempts, new_run_id, open_run_output, save_hit, Provenance, ResultsLock, VanityResult,
+};
+use crate::verify::confirm_result;
+
+/// Alphabet for generated seeds, must match `seed_alphabet` in vanity.cl
+pub const SEED_ALPHABET: &[u8; 62] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Number of counter characters after the 8-character run prefix
+const COUNTER_LEN: usize = 8;
+
+/// Seeds per run prefix before a new prefix is drawn (62^8)
+const INDEXES_PER_PREFIX: u64 = 218_340_105_584_896;
+
+/// Seed for `index`: the run prefix followed by the index in fixed-width base62
+pub fn seed_for_index(run_prefix: &[u8; 8], mut index: u64) -> String {
+    let mut seed = [0u8; 8 + COUNTER_LEN];
+    seed[..8].copy_from_slice(run_prefix);
+    for i in (0..COUNTER_LEN).rev() {
+        seed[8 + i] = SEED_ALPHABET[(index % 62) as usize];
+        index /= 62;
+    }
+    String::from_utf8(seed.to_vec()).expect("seed alphabet is ASCII")
+}
+
+fn random_run_prefix() -> [u8; 8] {
+    let mut rng = rand::thread_rng();
+    std::array::from_fn(|_| SEED_ALPHABET[rng.gen_range(0..SEED_ALPHABET.len())])
+}
+
+/// Searches for `Pubkey::create_with_seed(base, seed, owner)` addresses. These are a
+/// single SHA-256 with no Ed25519 work, so they are far cheaper to grind than keypairs.
+pub struct SeedAddressGenerator {
+    base: Pubkey,
+    owner: Pubkey,
+    pattern: Pattern,
+    count: usize,
+    device: Option<usize>,
+    output_path: String,
+    results: Vec<VanityResult>,
+    /// Header of the output if it is an encrypted keystore, kept for every save
+    keystore: Option<KeystoreHeader>,
+    _lock: ResultsLock,
+}
+
+impl SeedAddressGenerator {
+    pub fn new(
+        base: Pubkey,
+        owner: Pubkey,
+        pattern: Pattern,
+        count: usize,
+        device: Option<usize>,
+        output_path: String,
+    ) -> Result<Self> {
+        // The runtime rejects owners that would make the address collide with a PDA
+        Pubkey::create_with_seed(&base, "", &owner)
+            .map_err(|e| anyhow!("Invalid owner {}: {}", owner, e))?;
+
+        let lock = ResultsLock::acquire(&output_path)?;
+        let (results, keystore) = open_run_output(&output_path)?;
+
+        Ok(SeedAddressGenerator {
+            base,
+            owner,
+            pattern,
+            count,
+            device,
+            output_path,
+            results,
+            keystore,
+            _lock: lock,
+        })
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        println!("🚀 Starting create_with_seed vanity search");
+        println!("Base: {}", self.base);
+        println!("Owner: {}", self.owner);
+        println!("Pattern: {}", self.pattern);
+        println!("Target count: {}", self.count);
+        println!("Case sensitive: {}", self.pattern.case_sensitive());
+        println!(
+            "Estimated attempts needed: {}",
+            format_attempts(estimate_attempts_needed(&self.pattern))
+        );
+
+        // The kernel only matches a literal prefix and suffix
+        let affixes = self.pattern.simple_affixes();
+        let mut provenance = Provenance::new(
+            &new_run_id(),
+            "cpu",
+            "create-with-seed",
+            self.pattern.case_sensitive(),
+        );
+        let kernel = match (self.device, &affixes) {
+            (Some(device), Some(_)) => {
+                println!("Using OpenCL device: {}", device);
+                let opencl_manager = OpenCLManager::new()?;
+                provenance.backend = "opencl".to_string();
+                provenance.device = opencl_manager.device_name(device);
+                Some(opencl_manager.create_vanity_kernel(device)?)
+            }
+            (Some(_), None) => {
+                println!("OpenCL only supports plain prefix/suffix patterns, using CPU-only mode");
+                None
+            }
+            (None, _) => {
+                println!("Using CPU-only mode");
+                None
+            }
+        };
+        let (prefix, suffix) = affixes.unwrap_or_default();
+        let byte_mask = self.pattern.byte_mask();
+
+        let batch_size = 1 << 22;
+        let start_time = Instant::now();
+        let mut run_prefix = random_run_prefix();
+        let mut next_index = 0u64;
+        let mut total_attempts = 0u64;
+        let mut found_count = 0;
+
+        while found_count < self.count {
+            if next_index + batch_size as u64 > INDEXES_PER_PREFIX {
+                run_prefix = random_run_prefix();
+                next_index = 0;
+            }
+
+            let hits: Vec<u64> = match &kernel {
+                Some(kernel) => kernel.grind_seed_addresses(
+                    &self.base.to_bytes(),
+                    &self.owner.to_bytes(),
+                    &run_prefix,
+                    next_index,
+                    batch_size,
+                    &prefix,
+                    &suffix,
+                    self.pattern.case_sensitive(),
+                    &byte_mask.value,
+                    &byte_mask.mask,
+                )?,
+                None => (next_index..next_index + batch_size as u64)
+                    .into_par_iter()
+                    .filter(|&index| {
+                        let seed = seed_for_index(&run_prefix, index);
+                        let address = Pubkey::create_with_seed(&self.base, &seed, &self.owner)
+                            .expect("owner validated in new");
+                        check_pattern_match(&address, &self.pattern)
+                    })
+                    .collect(),
+            };
+
+            for index in hits {
+                let seed = seed_for_index(&run_prefix, index);
+                let address = Pubkey::create_with_seed(&self.base, &seed, &self.owner)?;
+
+                // Re-check on the CPU so a faulty kernel can never produce a wrong result
+                if !check_primary_match(&address, &self.pattern) {
+                    println!("⚠️  Discarding unverified OpenCL hit for seed {}", seed);
+                    continue;
+                }
+                if !check_pattern_match(&address, &self.pattern) {
+                    continue; // Rejected by the structural filters
+                }
+
+                let fuzzy_match = self.pattern.fuzzy_match(&address.to_string());
+                let result = VanityResult {
+                    public_key: address.to_string(),
+                    pattern_matched: self.pattern.label().to_string(),
+                    matched_word: self.pattern.matched_word(&address.to_string()),
+                    fuzzy_text: fuzzy_match.as_ref().map(|matched| matched.text.clone()),
+                    edit_distance: fuzzy_match.map(|matched| matched.distance),
+                    attempts: total_attempts + index - next_index + 1,
+                    found_at: chrono::Utc::now(),
+                    base: Some(self.base.to_string()),
+                    seed: Some(seed),
+                    owner: Some(self.owner.to_string()),
+                    pattern: Some(self.pattern.to_string()),
+                    match_positions: Some(self.pattern.matched_spans(&address.to_string())),
+                    provenance: Some(provenance.clone()),
+                    ..Default::default()
+                };
+                if !confirm_result(&result, None) {
+                    continue;
+                }
+                if !save_hit(
+                    &mut self.results,
+                    result,
+                    &self.output_path,
+                    self.keystore.as_ref(),
+                )? {
+                    continue;
+                }
+
+                found_count += 1;
+                if found_count >= self.count {
+                    break;
+                }
+            }
+
+            next_index += batch_size as u64;
+            total_attempts += batch_size as u64;
+
+            let timestamp = chrono::Utc::now().format("%H:%M:%S");
+            let speed_mhps = total_attempts as f64 / start_time.elapsed().as_secs_f64() / 1e6;
+            println!("[{}] Speed: {:.2} MH/s", timestamp, speed_mhps);
+        }
+
+        println!("\n🎉 Search completed!");
+        println!("Total time: {:.2}s", start_time.elapsed().as_secs_f64());
+        println!("Total attempts: {}", format_attempts(total_attempts));
+
+        // Seed and program-derived addresses have no secrets to redact
+        display_results(&self.results, false);
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{anyhow, bail, Result};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+use std::fs;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// Start of every encoded share, so shares are told apart from keys and other base58
+pub const SHARE_PREFIX: &str = "vshare1";
+
+/// Version, threshold, index, public key, share bytes and checksum
+const SHARE_LEN: usize = 1 + 1 + 1 + 32 + 32 + 4;
+
+/// `M-of-N` split of a seed: any `threshold` of the `shares` rebuild it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShamirScheme {
+    pub threshold: u8,
+    pub shares: u8,
+}
+
+impl FromStr for ShamirScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (threshold, shares) = s
+            .split_once("-of-")
+            .ok_or_else(|| anyhow!("Shamir scheme must look like 3-of-5"))?;
+        let threshold: u8 = threshold
+            .parse()
+            .map_err(|_| anyhow!("Invalid threshold '{}'", threshold))?;
+        let shares: u8 = shares
+            .parse()
+            .map_err(|_| anyhow!("Invalid share count '{}'", shares))?;
+        if threshold < 2 || threshold > shares {
+            bail!("Threshold must be between 2 and the share count (at most 255)");
+        }
+        Ok(ShamirScheme { threshold, shares })
+    }
+}
+
+/// One share of a split seed, carrying the public key it rebuilds
+pub struct Share {
+    threshold: u8,
+    index: u8,
+    public_key: Pubkey,
+    value: Zeroizing<[u8; 32]>,
+}
+
+impl Share {
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// `vshare1` followed by the base58 of the share and a 4-byte SHA-256 checksum
+    pub fn encode(&self) -> String {
+        let mut payload = Zeroizing::new(Vec::with_capacity(SHARE_LEN));
+        payload.extend_from_slice(&[1, self.threshold, self.index]);
+        payload.extend_from_slice(self.public_key.as_ref());
+        payload.extend_from_slice(&self.value[..]);
+        let check = checksum(&payload);
+        payload.extend_from_slice(&check);
+        format!(
+            "{}{}",
+            SHARE_PREFIX,
+            bs58::encode(&payload[..]).into_string()
+        )
+    }
+
+    pub fn decode(text: &str) -> Result<Self> {
+        let encoded = text
+            .trim()
+            .strip_prefix(SHARE_PREFIX)
+            .ok_or_else(|| anyhow!("Not a share: missing the {} prefix", SHARE_PREFIX))?;
+        let payload = Zeroizing::new(
+            bs58::decode(encoded)
+                .into_vec()
+                .map_err(|e| anyhow!("Invalid share encoding: {}", e))?,
+        );
+        if payload.len() != SHARE_LEN {
+            bail!("Share has {} bytes instead of {}", payload.len(), SHARE_LEN);
+        }
+        let (body, check) = payload.split_at(SHARE_LEN - 4);
+        if checksum(body)[..] != check[..] {
+            bail!("Share checksum mismatch, check it for typos");
+        }
+        if body[0] != 1 {
+            bail!("Unsupported share version {}", body[0]);
+        }
+        if body[1] < 2 || body[2] == 0 {
+            bail!("Invalid share threshold {} or index {}", body[1], body[2]);
+        }
+        let mut value = Zeroizing::new([0u8; 32]);
+        value.copy_from_slice(&body[35..67]);
+        Ok(Share {
+            threshold: body[1],
+            index: body[2],
+            public_key: Pubkey::try_from(&body[3..35]).unwrap(),
+            value,
+        })
+    }
+
+    /// A share given on the command line, either encoded or as a file holding one
+    pub fn read(arg: &str) -> Result<Self> {
+        if arg.starts_with(SHARE_PREFIX) {
+            return Share::decode(arg);
+        }
+        let content = Zeroizing::new(
+            fs::read_to_string(arg).map_err(|e| anyhow!("Cannot read share {}: {}", arg, e))?,
+        );
+        Share::decode(&content).map_err(|e| anyhow!("{}: {}", arg, e))
+    }
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(payload);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse as a^254, since a^255 = 1 for every non-zero a
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    for _ in 0..254 {
+        result = gf_mul(result, a);
+    }
+    result
+}
+
+/// Splits the 32-byte seed of a keypair into shares at x = 1..=N of random polynomials of
+/// degree M-1, one per seed byte, whose constant terms are the seed
+pub fn split(keypair: &Keypair, scheme: ShamirScheme) -> Vec<Share> {
+    let bytes = Zeroizing::new(keypair.to_bytes());
+    let mut rng = rand::thread_rng();
+    let coefficients: Vec<Zeroizing<Vec<u8>>> = bytes[..32]
+        .iter()
+        .map(|&secret| {
+            let mut polynomial = Zeroizing::new(vec![secret]);
+            polynomial.extend((1..scheme.threshold).map(|_| rng.gen::<u8>()));
+            polynomial
+        })
+        .collect();
+
+    (1..=scheme.shares)
+        .map(|x| {
+            let mut value = Zeroizing::new([0u8; 32]);
+            for (byte, polynomial) in value.iter_mut().zip(&coefficients) {
+                // Horner's rule from the highest coefficient down
+                *byte = polynomial
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, &coefficient| gf_mul(acc, x) ^ coefficient);
+            }
+            Share {
+                threshold: scheme.threshold,
+                index: x,
+                public_key: keypair.pubkey(),
+                value,
+            }
+        })
+        .collect()
+}
+
+/// Rebuilds the keypair by Lagrange interpolation at x = 0 and checks that it has the
+/// public key recorded in the shares, so a wrong or corrupted share never yields a key
+pub fn combine(shares: &[Share]) -> Result<Keypair> {
+    let Some(first) = shares.first() else {
+        bail!("No shares given");
+    };
+    if shares
+        .iter()
+        .any(|share| share.public_key != first.public_key || share.threshold != first.threshold)
+    {
+        bail!("Shares belong to different keys or splits");
+    }
+    let mut used: Vec<&Share> = Vec::new();
+    for share in shares {
+        if used.iter().any(|other| other.index == share.index) {
+            bail!("Share {} given twice", share.index);
+        }
+        used.push(share);
+    }
+    if used.len() < first.threshold as usize {
+        bail!(
+            "{} shares of {} needed, got {}",
+            first.threshold,
+            first.public_key,
+            used.len()
+        );
+    }
+    used.truncate(first.threshold as usize);
+
+    let mut seed = Zeroizing::new([0u8; 32]);
+    for share in &used {
+        // Basis polynomial of this share evaluated at 0: product of x_k / (x_k - x_j)
+        let basis = used
+            .iter()
+            .filter(|other| other.index != share.index)
+            .fold(1, |acc, other| {
+                gf_mul(acc, gf_mul(other.index, gf_inv(other.index ^ share.index)))
+            });
+        for (byte, value) in seed.iter_mut().zip(share.value.iter()) {
+            *byte ^= gf_mul(basis, *value);
+        }
+    }
+
+    let keypair =
+        keypair_from_seed(&seed[..]).map_err(|e| anyhow!("Invalid rebuilt seed: {}", e))?;
+    if keypair.pubkey() != first.public_key {
+        bail!(
+            "Shares rebuild {} instead of the recorded {}",
+            keypair.pubkey(),
+            first.public_key
+        );
+    }
+    Ok(keypair)
+}
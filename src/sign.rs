@@ -0,0 +1,152 @@
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use solana_sdk::offchain_message::OffchainMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use std::fs;
+use std::io::Read;
+
+use crate::export::result_keypair;
+use crate::keystore::unlock_results;
+
+/// Keypair of one stored result, decrypting the keystore and re-deriving hierarchical keys
+/// as needed. Nothing here touches the network.
+pub fn load_signer(
+    input: &str,
+    public_key: &str,
+    master_secret: Option<&[u8; 32]>,
+    passphrase_fd: Option<i32>,
+) -> Result<Keypair> {
+    let results = unlock_results(input, &[public_key.to_string()], passphrase_fd)?;
+    let result = &results[0];
+    if result.age_secret.is_some() {
+        bail!(
+            "The key of {} is encrypted to an age recipient, decrypt it with age -d first",
+            public_key
+        );
+    }
+    result_keypair(result, master_secret)?.ok_or_else(|| {
+        anyhow!(
+            "{} is a seed or program-derived address and has no private key",
+            public_key
+        )
+    })
+}
+
+/// Text given on the command line, or the bytes of a file (`-` for stdin)
+pub fn read_message(text: Option<String>, file: Option<String>) -> Result<Vec<u8>> {
+    match (text, file) {
+        (Some(text), _) => Ok(text.into_bytes()),
+        (None, Some(file)) => read_input(&file),
+        (None, None) => bail!("Give the message with --message or --message-file"),
+    }
+}
+
+fn read_input(path: &str) -> Result<Vec<u8>> {
+    if path == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        return Ok(bytes);
+    }
+    fs::read(path).map_err(|e| anyhow!("Cannot read {}: {}", path, e))
+}
+
+/// Signs the raw bytes, or the message wrapped in a version 0 Solana off-chain message
+/// header (`\xffsolana offchain`, version, format, length) as `solana sign-offchain-message` does
+pub fn sign_message(keypair: &Keypair, message: &[u8], offchain: bool) -> Result<Signature> {
+    if offchain {
+        let message = OffchainMessage::new(0, message)
+            .map_err(|e| anyhow!("Invalid off-chain message: {}", e))?;
+        return message
+            .sign(keypair)
+            .map_err(|e| anyhow!("Cannot sign off-chain message: {}", e));
+    }
+    Ok(keypair.sign_message(message))
+}
+
+pub fn verify_message(
+    public_key: &Pubkey,
+    message: &[u8],
+    signature: &Signature,
+    offchain: bool,
+) -> Result<bool> {
+    if offchain {
+        let message = OffchainMessage::new(0, message)
+            .map_err(|e| anyhow!("Invalid off-chain message: {}", e))?;
+        return message
+            .verify(public_key, signature)
+            .map_err(|e| anyhow!("Cannot verify off-chain message: {}", e));
+    }
+    Ok(signature.verify(public_key.as_ref(), message))
+}
+
+/// A legacy or versioned transaction from base64 (or a file holding it, `-` for stdin)
+pub fn decode_transaction(arg: &str) -> Result<VersionedTransaction> {
+    let text = if arg == "-" || std::path::Path::new(arg).is_file() {
+        String::from_utf8(read_input(arg)?)?
+    } else {
+        arg.to_string()
+    };
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(text.trim())
+        .map_err(|e| anyhow!("Transaction is not base64: {}", e))?;
+    bincode::deserialize(&bytes).map_err(|e| anyhow!("Invalid serialized transaction: {}", e))
+}
+
+pub fn encode_transaction(transaction: &VersionedTransaction) -> Result<String> {
+    Ok(base64::engine::general_purpose::STANDARD.encode(bincode::serialize(transaction)?))
+}
+
+/// Required signers of the message, in signature order
+fn signers(transaction: &VersionedTransaction) -> &[Pubkey] {
+    let keys = transaction.message.static_account_keys();
+    let required = transaction.message.header().num_required_signatures as usize;
+    &keys[..required.min(keys.len())]
+}
+
+/// Adds the keypair's signature in its slot, leaving the blockhash and other signatures as
+/// they are. Returns the signature and how many signatures are still missing.
+pub fn sign_transaction(
+    transaction: &mut VersionedTransaction,
+    keypair: &Keypair,
+) -> Result<(Signature, usize)> {
+    let signers = signers(transaction).to_vec();
+    let index = signers
+        .iter()
+        .position(|signer| *signer == keypair.pubkey())
+        .ok_or_else(|| {
+            anyhow!(
+                "{} is not a required signer of the transaction",
+                keypair.pubkey()
+            )
+        })?;
+    transaction
+        .signatures
+        .resize(signers.len(), Signature::default());
+    let signature = keypair.sign_message(&transaction.message.serialize());
+    transaction.signatures[index] = signature;
+    let missing = transaction
+        .signatures
+        .iter()
+        .filter(|signature| **signature == Signature::default())
+        .count();
+    Ok((signature, missing))
+}
+
+/// Every required signer with whether its signature is valid, or None if it has not signed
+pub fn verify_transaction(transaction: &VersionedTransaction) -> Vec<(Pubkey, Option<bool>)> {
+    let message = transaction.message.serialize();
+    signers(transaction)
+        .iter()
+        .enumerate()
+        .map(|(index, signer)| {
+            let valid = transaction
+                .signatures
+                .get(index)
+                .filter(|signature| **signature != Signature::default())
+                .map(|signature| signature.verify(signer.as_ref(), &message));
+            (*signer, valid)
+        })
+        .collect()
+}
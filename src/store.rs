@@ -0,0 +1,450 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Statement, Transaction};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+use crate::keystore::KeystoreHeader;
+use crate::utils::{
+    load_results_file, save_results_with_header, warn_if_world_readable, VanityResult,
+    RESULTS_SCHEMA_VERSION,
+};
+
+/// Output paths with these extensions are SQLite databases instead of JSON files
+const DATABASE_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    public_key TEXT PRIMARY KEY,
+    pattern TEXT NOT NULL,
+    run_id TEXT,
+    found_at TEXT NOT NULL,
+    label TEXT,
+    notes TEXT,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_pattern ON results (pattern);
+CREATE INDEX IF NOT EXISTS results_run_id ON results (run_id);
+CREATE INDEX IF NOT EXISTS results_found_at ON results (found_at);
+CREATE INDEX IF NOT EXISTS results_label ON results (label);
+";
+
+pub fn is_database(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| DATABASE_EXTENSIONS.contains(&extension))
+}
+
+/// Opens or creates a results database, only readable by the current user
+fn open(path: &str) -> Result<Connection> {
+    if Path::new(path).exists() {
+        warn_if_world_readable(path);
+    } else {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .map_err(|e| anyhow!("Cannot create {}: {}", path, e))?;
+    }
+    let connection =
+        Connection::open(path).map_err(|e| anyhow!("Cannot open database {}: {}", path, e))?;
+    // Overwrite the pages of deleted rows so removed secrets do not linger in the file
+    connection.pragma_update(None, "secure_delete", true)?;
+    connection.execute_batch(SCHEMA)?;
+
+    let version: Option<String> = connection
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match version.map(|version| version.parse::<u32>()) {
+        None => {
+            connection.execute(
+                "INSERT INTO meta (key, value) VALUES ('schema_version', ?1)",
+                [RESULTS_SCHEMA_VERSION.to_string()],
+            )?;
+        }
+        Some(Ok(version)) if version <= RESULTS_SCHEMA_VERSION => {}
+        Some(version) => bail!(
+            "{} uses results schema version {}, this build reads up to {}; upgrade to load it",
+            path,
+            version.map_or_else(|_| "?".to_string(), |version| version.to_string()),
+            RESULTS_SCHEMA_VERSION
+        ),
+    }
+    Ok(connection)
+}
+
+/// Keystore header of an encrypted database, without reading its results
+pub fn header(path: &str) -> Result<Option<KeystoreHeader>> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    keystore_header(&open(path)?)
+}
+
+fn keystore_header(connection: &Connection) -> Result<Option<KeystoreHeader>> {
+    let header: Option<String> = connection
+        .query_row("SELECT value FROM meta WHERE key = 'keystore'", [], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    Ok(header
+        .map(|header| serde_json::from_str(&header))
+        .transpose()?)
+}
+
+fn decode(data: String) -> Result<VanityResult> {
+    let data = Zeroizing::new(data);
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// Every result in the database, oldest first, and the keystore header if it is encrypted
+pub fn load(path: &str) -> Result<(Vec<VanityResult>, Option<KeystoreHeader>)> {
+    if !Path::new(path).exists() {
+        return Ok((Vec::new(), None));
+    }
+    let connection = open(path)?;
+    let results = query_rows(&connection, &ResultFilter::default())?;
+    Ok((results, keystore_header(&connection)?))
+}
+
+const INSERT_ROW: &str =
+    "INSERT INTO results (public_key, pattern, run_id, found_at, label, notes, data)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)";
+
+fn execute_row(statement: &mut Statement, result: &VanityResult) -> Result<usize> {
+    let data = Zeroizing::new(serde_json::to_string(result)?);
+    Ok(statement.execute(params![
+        result.public_key,
+        result.pattern.as_deref().unwrap_or(&result.pattern_matched),
+        result
+            .provenance
+            .as_ref()
+            .map(|provenance| &provenance.run_id),
+        result.found_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        result.label,
+        result.notes,
+        data.as_str(),
+    ])?)
+}
+
+fn set_keystore(transaction: &Transaction, keystore: Option<&KeystoreHeader>) -> Result<()> {
+    match keystore {
+        Some(header) => transaction.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('keystore', ?1)",
+            [serde_json::to_string(header)?],
+        )?,
+        None => transaction.execute("DELETE FROM meta WHERE key = 'keystore'", [])?,
+    };
+    Ok(())
+}
+
+/// Makes the database hold exactly `results`, in one transaction so a crash keeps the old
+/// contents. Used by the `results` commands; searches add their hits with `insert`.
+pub fn save(path: &str, results: &[VanityResult], keystore: Option<&KeystoreHeader>) -> Result<()> {
+    let mut connection = open(path)?;
+    let transaction = connection.transaction()?;
+    {
+        let mut upsert = transaction.prepare(&format!(
+            "{} ON CONFLICT (public_key) DO UPDATE SET
+                 pattern = excluded.pattern, run_id = excluded.run_id,
+                 found_at = excluded.found_at, label = excluded.label,
+                 notes = excluded.notes, data = excluded.data
+             WHERE data != excluded.data",
+            INSERT_ROW
+        ))?;
+        for result in results {
+            execute_row(&mut upsert, result)?;
+        }
+
+        let stored: Vec<String> = transaction
+            .prepare("SELECT public_key FROM results")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        let kept: HashSet<&str> = results
+            .iter()
+            .map(|result| result.public_key.as_str())
+            .collect();
+        let mut delete = transaction.prepare("DELETE FROM results WHERE public_key = ?1")?;
+        for public_key in stored {
+            if !kept.contains(public_key.as_str()) {
+                delete.execute([public_key])?;
+            }
+        }
+    }
+    set_keystore(&transaction, keystore)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Adds one hit without reading the stored rows. Returns false if its public key is already
+/// in the database, which the primary key detects.
+pub fn insert(
+    path: &str,
+    result: &VanityResult,
+    keystore: Option<&KeystoreHeader>,
+) -> Result<bool> {
+    let mut connection = open(path)?;
+    let transaction = connection.transaction()?;
+    let added = execute_row(
+        &mut transaction.prepare(&format!(
+            "{} ON CONFLICT (public_key) DO NOTHING",
+            INSERT_ROW
+        ))?,
+        result,
+    )? > 0;
+    set_keystore(&transaction, keystore)?;
+    transaction.commit()?;
+    Ok(added)
+}
+
+/// Deletes the rows of results that fell out of score mode's top list
+pub fn remove(path: &str, public_keys: &[String]) -> Result<()> {
+    let mut connection = open(path)?;
+    let transaction = connection.transaction()?;
+    {
+        let mut delete = transaction.prepare("DELETE FROM results WHERE public_key = ?1")?;
+        for public_key in public_keys {
+            delete.execute([public_key])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Which results `results list` and `results search` print
+#[derive(Default)]
+pub struct ResultFilter {
+    /// Exact pattern spec, or the label of results from before specs were recorded
+    pub pattern: Option<String>,
+    pub run_id: Option<String>,
+    pub label: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Case-insensitive text in the public key, pattern, label or notes
+    pub text: Option<String>,
+    /// Only the newest this many
+    pub limit: Option<usize>,
+}
+
+impl ResultFilter {
+    fn matches(&self, result: &VanityResult) -> bool {
+        let pattern = result.pattern.as_deref().unwrap_or(&result.pattern_matched);
+        let contains = |field: Option<&str>, text: &str| {
+            field.is_some_and(|field| field.to_lowercase().contains(&text.to_lowercase()))
+        };
+        self.pattern
+            .as_deref()
+            .is_none_or(|wanted| wanted == pattern)
+            && self.run_id.as_deref().is_none_or(|wanted| {
+                result
+                    .provenance
+                    .as_ref()
+                    .map(|provenance| provenance.run_id.as_str())
+                    == Some(wanted)
+            })
+            && self
+                .label
+                .as_deref()
+                .is_none_or(|wanted| result.label.as_deref() == Some(wanted))
+            && self.since.is_none_or(|since| result.found_at >= since)
+            && self.until.is_none_or(|until| result.found_at < until)
+            && self.text.as_deref().is_none_or(|text| {
+                contains(Some(&result.public_key), text)
+                    || contains(Some(pattern), text)
+                    || contains(result.label.as_deref(), text)
+                    || contains(result.notes.as_deref(), text)
+            })
+    }
+}
+
+/// Runs the filter in SQL, so only matching rows are read and decoded
+fn query_rows(connection: &Connection, filter: &ResultFilter) -> Result<Vec<VanityResult>> {
+    let mut conditions = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+    let mut condition = |sql: &str, value: String| {
+        values.push(value);
+        conditions.push(sql.replace('?', &format!("?{}", values.len())));
+    };
+    if let Some(pattern) = &filter.pattern {
+        condition("pattern = ?", pattern.clone());
+    }
+    if let Some(run_id) = &filter.run_id {
+        condition("run_id = ?", run_id.clone());
+    }
+    if let Some(label) = &filter.label {
+        condition("label = ?", label.clone());
+    }
+    if let Some(since) = filter.since {
+        condition(
+            "found_at >= ?",
+            since.to_rfc3339_opts(SecondsFormat::Micros, true),
+        );
+    }
+    if let Some(until) = filter.until {
+        condition(
+            "found_at < ?",
+            until.to_rfc3339_opts(SecondsFormat::Micros, true),
+        );
+    }
+    if let Some(text) = &filter.text {
+        condition(
+            "(instr(lower(public_key), lower(?)) OR instr(lower(pattern), lower(?))
+              OR instr(lower(ifnull(label, '')), lower(?)) OR instr(lower(ifnull(notes, '')), lower(?)))",
+            text.clone(),
+        );
+    }
+
+    let mut sql = "SELECT data, found_at FROM results".to_string();
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    // The newest `limit` rows, still returned oldest first
+    match filter.limit {
+        Some(limit) => {
+            sql = format!(
+                "SELECT data FROM ({} ORDER BY found_at DESC LIMIT {}) ORDER BY found_at",
+                sql, limit
+            )
+        }
+        None => sql.push_str(" ORDER BY found_at"),
+    }
+
+    let mut statement = connection.prepare(&sql)?;
+    let rows = statement.query_map(params_from_iter(values.iter()), |row| row.get(0))?;
+    rows.map(|data| decode(data?)).collect()
+}
+
+/// Results of a JSON file or database that pass the filter, oldest first
+pub fn query(path: &str, filter: &ResultFilter) -> Result<Vec<VanityResult>> {
+    if is_database(path) {
+        if !Path::new(path).exists() {
+            return Ok(Vec::new());
+        }
+        return query_rows(&open(path)?, filter);
+    }
+    let mut results: Vec<VanityResult> = load_results_file(path)?
+        .0
+        .into_iter()
+        .filter(|result| filter.matches(result))
+        .collect();
+    results.sort_by_key(|result| result.found_at);
+    if let Some(limit) = filter.limit {
+        results.drain(..results.len().saturating_sub(limit));
+    }
+    Ok(results)
+}
+
+/// Sets the label and notes of one result; an empty string clears them
+pub fn tag(
+    path: &str,
+    public_key: &str,
+    label: Option<String>,
+    notes: Option<String>,
+) -> Result<()> {
+    let (mut results, header) = load_results_file(path)?;
+    let result = results
+        .iter_mut()
+        .find(|result| result.public_key == public_key)
+        .ok_or_else(|| anyhow!("{} is not in {}", public_key, path))?;
+    if let Some(label) = label {
+        result.label = Some(label).filter(|label| !label.is_empty());
+    }
+    if let Some(notes) = notes {
+        result.notes = Some(notes).filter(|notes| !notes.is_empty());
+    }
+    save_results_with_header(&results, path, header.as_ref())
+}
+
+/// Removes results by public key, failing before any change if one is missing
+pub fn delete(path: &str, public_keys: &[String]) -> Result<usize> {
+    let (mut results, header) = load_results_file(path)?;
+    if let Some(missing) = public_keys.iter().find(|public_key| {
+        !results
+            .iter()
+            .any(|result| &&result.public_key == public_key)
+    }) {
+        bail!("{} is not in {}", missing, path);
+    }
+    let before = results.len();
+    results.retain(|result| !public_keys.contains(&result.public_key));
+    save_results_with_header(&results, path, header.as_ref())?;
+    Ok(before - results.len())
+}
+
+/// Adds the results of `sources` to `destination`, skipping public keys it already has but
+/// taking over labels and notes it lacks. Returns how many results were added.
+pub fn merge(destination: &str, sources: &[String]) -> Result<usize> {
+    let (mut results, mut header) = load_results_file(destination)?;
+    let mut added = 0;
+    for source in sources {
+        let (incoming, source_header) = load_results_file(source)?;
+        match (&header, source_header) {
+            (_, None) => {}
+            (None, Some(source_header)) => {
+                if results.iter().any(|result| !result.private_key.is_empty()) {
+                    bail!(
+                        "{} is an encrypted keystore and {} holds plaintext keys, merge into another file",
+                        source,
+                        destination
+                    );
+                }
+                header = Some(source_header);
+            }
+            (Some(header), Some(source_header)) => {
+                if header.salt != source_header.salt {
+                    bail!(
+                        "{} and {} are encrypted with different keystores",
+                        source,
+                        destination
+                    );
+                }
+            }
+        }
+        if header.is_some() && incoming.iter().any(|result| !result.private_key.is_empty()) {
+            bail!(
+                "{} holds plaintext keys and {} is an encrypted keystore",
+                source,
+                destination
+            );
+        }
+
+        let (mut new, mut duplicates) = (0, 0);
+        for result in incoming {
+            match results
+                .iter_mut()
+                .find(|existing| existing.public_key == result.public_key)
+            {
+                Some(existing) => {
+                    existing.label = existing.label.take().or(result.label);
+                    existing.notes = existing.notes.take().or(result.notes);
+                    duplicates += 1;
+                }
+                None => {
+                    results.push(result);
+                    new += 1;
+                }
+            }
+        }
+        println!(
+            "{}: {} new, {} already in {}",
+            source, new, duplicates, destination
+        );
+        added += new;
+    }
+    save_results_with_header(&results, destination, header.as_ref())?;
+    Ok(added)
+}
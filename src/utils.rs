@@ -1,21 +1,300 @@
-use anyhow::Result;
-use bs58;
+use anyhow::{anyhow, bail, Result};
+use bip39::{Language, Mnemonic, MnemonicType};
+use hmac::{Hmac, Mac};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use solana_sdk::{
+    derivation_path::DerivationPath,
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    signature::Keypair,
+    signer::keypair::{
+        generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed,
+        keypair_from_seed_and_derivation_path,
+    },
 };
+use std::cell::OnceCell;
+use std::collections::HashSet;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
+use std::sync::Once;
+use zeroize::Zeroizing;
 
-#[derive(Serialize, Deserialize, Clone)]
+use crate::chain::AddressFormat;
+use crate::keystore::{Keystore, KeystoreHeader, SealedSecret};
+use crate::pattern::{Node, Pattern};
+use crate::secret::{Secret, REDACTED};
+use crate::store::{self, is_database};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct VanityResult {
     pub public_key: String,
-    pub private_key: String,
+    /// Empty for hierarchical results, which are re-derived from the master secret
+    #[serde(default, skip_serializing_if = "Secret::is_empty")]
+    pub private_key: Secret,
     pub pattern_matched: String,
     pub attempts: u64,
     pub found_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_index: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<Secret>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bip39_passphrase: Option<Secret>,
+    /// Base, seed and owner of a `Pubkey::create_with_seed` address, which has no private key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Program ID, seed components and bump of a program-derived address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seeds: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bump: Option<u8>,
+    /// Mint, token program and derived associated token account of the owner keypair
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_program: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub associated_token_account: Option<String>,
+    /// Dictionary word the address matched in wordlist mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_word: Option<String>,
+    /// Best-effort score in `--score` mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// Address text that matched a `--max-distance` term and its edit distance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuzzy_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edit_distance: Option<f64>,
+    /// Seconds into the run when a progressive-mode prefix was reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_secs: Option<f64>,
+    /// Chain other than Solana and the address the public key has there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_address: Option<String>,
+    /// Private key, mnemonic and BIP39 passphrase in an encrypted keystore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed_secret: Option<SealedSecret>,
+    /// Private key, mnemonic and BIP39 passphrase as an armored age file for `--recipient`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_secret: Option<String>,
+    /// Full pattern spec the search ran with, and the byte ranges of the address it matched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_positions: Option<Vec<std::ops::Range<usize>>>,
+    /// Tool, run and backend that found the result
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// Free-form label and notes set with `results tag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Version of the results file layout written by this build. Version 1 files, a bare array
+/// or a keystore object without `schema_version`, are migrated when loaded.
+pub const RESULTS_SCHEMA_VERSION: u32 = 2;
+
+/// Where a result came from, so results merged from many runs can be told apart
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct Provenance {
+    pub tool_version: String,
+    /// Random ID shared by every result of one invocation
+    pub run_id: String,
+    /// `cpu` or `opencl`
+    pub backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// `random`, `hierarchical`, `bip39-mnemonic`, `wallet-paths`, `create-with-seed` or `pda`
+    pub seed_derivation: String,
+    pub case_sensitive: bool,
+}
+
+impl Provenance {
+    pub fn new(run_id: &str, backend: &str, seed_derivation: &str, case_sensitive: bool) -> Self {
+        Provenance {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            run_id: run_id.to_string(),
+            backend: backend.to_string(),
+            device: None,
+            seed_derivation: seed_derivation.to_string(),
+            case_sensitive,
+        }
+    }
+}
+
+/// 64-bit random hex ID for one invocation
+pub fn new_run_id() -> String {
+    format!("{:016x}", rand::thread_rng().gen::<u64>())
+}
+
+/// On-disk results: a versioned object, optionally with the header of an encrypted keystore
+/// whose results carry sealed secrets. The version 1 layouts are still read.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ResultsFile {
+    Versioned {
+        schema_version: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keystore: Option<KeystoreHeader>,
+        results: Vec<VanityResult>,
+    },
+    V1Plain(Vec<VanityResult>),
+    V1Encrypted {
+        keystore: KeystoreHeader,
+        results: Vec<VanityResult>,
+    },
+}
+
+/// Only the version of a results file, ignoring the rest
+#[derive(Deserialize)]
+struct SchemaProbe {
+    schema_version: Option<u32>,
+}
+
+/// How candidate keypairs are produced during a search
+#[derive(Clone)]
+pub enum SeedDerivation {
+    /// Fresh random keypair for every attempt
+    Random,
+    /// Seed for index `i` is HMAC-SHA256(master_secret, i as u64 little-endian)
+    Hierarchical {
+        master_secret: Zeroizing<[u8; 32]>,
+        start_index: u64,
+    },
+    /// Random BIP39 mnemonic per attempt, key derived at `m/44'/501'/0'/0'`
+    Mnemonic {
+        word_count: usize,
+        passphrase: Secret,
+    },
+    /// Walks `m/44'/501'/i'/j'` of a fixed wallet for each of its passphrases
+    WalletPaths {
+        wallets: Vec<WalletSeed>,
+        accounts: u32,
+        changes: u32,
+    },
+}
+
+/// BIP39 seed of the user's mnemonic under one passphrase
+#[derive(Clone)]
+pub struct WalletSeed {
+    pub passphrase: Secret,
+    pub seed: Zeroizing<Vec<u8>>,
+}
+
+/// Relative cost of one mnemonic attempt compared to a plain keypair: PBKDF2-HMAC-SHA512
+/// with 2048 rounds dominates, followed by four SLIP-0010 derivation steps
+pub const MNEMONIC_COST_FACTOR: f64 = 50.0;
+
+impl SeedDerivation {
+    /// Name recorded in result provenance
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeedDerivation::Random => "random",
+            SeedDerivation::Hierarchical { .. } => "hierarchical",
+            SeedDerivation::Mnemonic { .. } => "bip39-mnemonic",
+            SeedDerivation::WalletPaths { .. } => "wallet-paths",
+        }
+    }
+
+    /// How many plain keypair generations one attempt costs
+    pub fn cost_factor(&self) -> f64 {
+        match self {
+            SeedDerivation::Random
+            | SeedDerivation::Hierarchical { .. }
+            | SeedDerivation::WalletPaths { .. } => 1.0,
+            SeedDerivation::Mnemonic { .. } => MNEMONIC_COST_FACTOR,
+        }
+    }
+
+    /// Number of candidates when the keyspace is finite
+    pub fn keyspace_size(&self) -> Option<u64> {
+        match self {
+            SeedDerivation::WalletPaths {
+                wallets,
+                accounts,
+                changes,
+            } => Some(wallets.len() as u64 * *accounts as u64 * *changes as u64),
+            _ => None,
+        }
+    }
+
+    /// Scales a batch size tuned for plain keypairs so batches take similar time
+    pub fn batch_size(&self, base: usize) -> usize {
+        ((base as f64 / self.cost_factor()) as usize).max(1)
+    }
+
+    pub fn supports_opencl(&self) -> bool {
+        matches!(
+            self,
+            SeedDerivation::Random | SeedDerivation::Hierarchical { .. }
+        )
+    }
+}
+
+/// Splits a keyspace position into (wallet, account, change), varying the passphrase fastest
+/// so every passphrase is tried on the cheapest accounts first
+pub fn wallet_path_at(index: u64, wallets: usize, changes: u32) -> (usize, u32, u32) {
+    let wallet = (index % wallets as u64) as usize;
+    let path = index / wallets as u64;
+    (
+        wallet,
+        (path / changes as u64) as u32,
+        (path % changes as u64) as u32,
+    )
+}
+
+/// Reads a seed phrase from a file, or stdin when no file is given, and validates it
+pub fn read_mnemonic(path: Option<&str>) -> Result<Secret> {
+    let content = Zeroizing::new(match path {
+        Some(path) if path != "-" => fs::read_to_string(path)?,
+        _ => {
+            eprintln!("Enter seed phrase:");
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line
+        }
+    });
+
+    let phrase = Secret::new(content.split_whitespace().collect::<Vec<_>>().join(" "));
+    Mnemonic::validate(phrase.expose(), Language::English)
+        .map_err(|e| anyhow!("Invalid seed phrase: {}", e))?;
+    Ok(phrase)
+}
+
+/// Wallet derivation path for the first Solana account, as used by Phantom and Solflare
+pub fn solana_derivation_path() -> DerivationPath {
+    DerivationPath::new_bip44(Some(0), Some(0))
+}
+
+pub fn generate_mnemonic(word_count: usize) -> Result<String> {
+    let mnemonic_type = MnemonicType::for_word_count(word_count)
+        .map_err(|e| anyhow!("Invalid mnemonic word count {}: {}", word_count, e))?;
+    Ok(Mnemonic::new(mnemonic_type, Language::English).into_phrase())
+}
+
+pub fn keypair_from_mnemonic(
+    phrase: &str,
+    passphrase: &str,
+    derivation_path: &DerivationPath,
+) -> Result<Keypair> {
+    let seed = generate_seed_from_seed_phrase_and_passphrase(phrase, passphrase);
+    keypair_from_seed_and_derivation_path(&seed, Some(derivation_path.clone()))
+        .map_err(|e| anyhow!("Failed to derive keypair: {}", e))
 }
 
 pub fn generate_keypair_from_seed(seed: u32) -> Keypair {
@@ -35,61 +314,586 @@ pub fn generate_keypair_from_seed(seed: u32) -> Keypair {
     })
 }
 
-pub fn check_pattern_match(
-    pubkey: &Pubkey,
-    starts_with: &Option<String>,
-    ends_with: &Option<String>,
-    case_sensitive: bool,
-) -> bool {
-    let pubkey_str = pubkey.to_string();
+pub fn derive_hd_seed(master_secret: &[u8; 32], index: u64) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(master_secret).expect("HMAC accepts any key size");
+    mac.update(&index.to_le_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+pub fn derive_hd_keypair(master_secret: &[u8; 32], index: u64) -> Keypair {
+    keypair_from_seed(&derive_hd_seed(master_secret, index)).expect("32-byte seed is always valid")
+}
+
+/// Loads a hex-encoded 32-byte master secret
+pub fn load_master_secret(path: &str) -> Result<Zeroizing<[u8; 32]>> {
+    let content = Zeroizing::new(
+        fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read master secret {}: {}", path, e))?,
+    );
+    let bytes = Zeroizing::new(hex::decode(content.trim())?);
+    let mut master_secret = Zeroizing::new([0u8; 32]);
+    if bytes.len() != master_secret.len() {
+        bail!("Master secret in {} must be 32 bytes of hex", path);
+    }
+    master_secret.copy_from_slice(&bytes);
+    Ok(master_secret)
+}
+
+/// Loads a hex-encoded 32-byte master secret, generating a new one if the file does not exist
+pub fn load_or_create_master_secret(path: &str) -> Result<Zeroizing<[u8; 32]>> {
+    if Path::new(path).exists() {
+        return load_master_secret(path);
+    }
+
+    let mut master_secret = Zeroizing::new([0u8; 32]);
+    rand::thread_rng().fill(&mut *master_secret);
+    write_private_file(path, Zeroizing::new(hex::encode(*master_secret)).as_bytes())?;
+    println!(
+        "🔑 Generated new master secret at {} - back this file up!",
+        path
+    );
+    Ok(master_secret)
+}
+
+/// Reads a secret such as a passphrase from a file, dropping the trailing newline
+pub fn read_secret_file(path: &str) -> Result<Secret> {
+    let content = Zeroizing::new(fs::read_to_string(path)?);
+    Ok(Secret::new(
+        content.trim_end_matches(['\r', '\n']).to_string(),
+    ))
+}
+
+/// Replaces a file with `contents`, readable only by the current user. The data is synced
+/// to a temporary file next to it and renamed over the target, so a crash leaves either the
+/// old or the new file, never a partial one.
+pub fn write_private_file(path: &str, contents: &[u8]) -> Result<()> {
+    let target = Path::new(path);
+    let name = target
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file path", path))?;
+    let temporary = target.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    // A leftover from a crashed run could have other permissions
+    let _ = fs::remove_file(&temporary);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&temporary).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&temporary, target)) {
+        let _ = fs::remove_file(&temporary);
+        bail!("Cannot write {}: {}", path, e);
+    }
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    {
+        let directory = match target.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        fs::File::open(directory)?.sync_all()?;
+    }
+    Ok(())
+}
 
-    let check_starts = if let Some(pattern) = starts_with {
-        if case_sensitive {
-            pubkey_str.starts_with(pattern)
-        } else {
-            let pubkey_lower = pubkey_str.to_lowercase();
-            let pattern_lower = pattern.to_lowercase();
-            pubkey_lower.starts_with(&pattern_lower)
+/// Exclusive advisory lock on `<output>.lock`, held for a whole search so two runs never
+/// replace each other's results file
+pub struct ResultsLock {
+    _file: fs::File,
+}
+
+impl ResultsLock {
+    pub fn acquire(output_path: &str) -> Result<Self> {
+        let lock_path = format!("{}.lock", output_path);
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| anyhow!("Cannot open lock file {}: {}", lock_path, e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            // SAFETY: flock only reads the descriptor, which `file` keeps open
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+                bail!(
+                    "{} is in use by another search (locked through {}), wait for it or pick another --output",
+                    output_path,
+                    lock_path
+                );
+            }
         }
-    } else {
-        true
-    };
+        // Released when the file is closed. The file stays, since removing it would let another
+        // run lock a new file while this one still holds the old.
+        Ok(ResultsLock { _file: file })
+    }
+}
 
-    let check_ends = if let Some(pattern) = ends_with {
-        if case_sensitive {
-            pubkey_str.ends_with(pattern)
-        } else {
-            let pubkey_lower = pubkey_str.to_lowercase();
-            let pattern_lower = pattern.to_lowercase();
-            pubkey_lower.ends_with(&pattern_lower)
+/// Warns, once per run, that a results file can be read by every user on the machine
+pub fn warn_if_world_readable(path: &str) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        static WARNED: Once = Once::new();
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.permissions().mode() & 0o004 != 0 {
+                WARNED.call_once(|| {
+                    eprintln!(
+                        "⚠️  {} is readable by every user on this machine, run chmod 600 {}",
+                        path, path
+                    )
+                });
+            }
         }
-    } else {
-        true
-    };
+    }
+}
 
-    check_starts && check_ends
+/// Evaluates a compiled pattern, encoding the address only if some term needs the string
+pub fn check_pattern_match(pubkey: &Pubkey, pattern: &Pattern) -> bool {
+    let address = Address::new(pubkey.to_bytes(), pattern.format());
+    evaluate(pattern.root(), &address) && passes_filters(pattern, &address)
 }
 
+/// Pattern match without the structural filters, as reported by the OpenCL kernel
+pub fn check_primary_match(pubkey: &Pubkey, pattern: &Pattern) -> bool {
+    evaluate(
+        pattern.root(),
+        &Address::new(pubkey.to_bytes(), pattern.format()),
+    )
+}
+
+/// Raw key and its lazily encoded address body on the pattern's chain
+struct Address {
+    key: [u8; 32],
+    format: &'static dyn AddressFormat,
+    encoded: OnceCell<String>,
+}
+
+impl Address {
+    fn new(key: [u8; 32], format: &'static dyn AddressFormat) -> Self {
+        Address {
+            key,
+            format,
+            encoded: OnceCell::new(),
+        }
+    }
+
+    fn encoded(&self) -> &str {
+        self.encoded.get_or_init(|| self.format.body(&self.key))
+    }
+}
+
+/// Filters only run after the primary match, so they cost nothing on misses
+fn passes_filters(pattern: &Pattern, address: &Address) -> bool {
+    pattern.filters().is_none_or(|filters| {
+        let encoded = address.encoded();
+        filters.accepts(encoded, &pattern.matched_spans(encoded))
+            && !filters
+                .excludes()
+                .iter()
+                .any(|exclude| evaluate(exclude.root(), address))
+    })
+}
+
+fn evaluate(node: &Node, address: &Address) -> bool {
+    let key = &address.key;
+    match node {
+        Node::Always => true,
+        Node::Term(term) => {
+            // Numeric fast paths assume the 43-44 character encoding of a key without leading zeros
+            if key[0] != 0 {
+                if let Some(matched) = term.match_numeric(key) {
+                    return matched;
+                }
+            }
+            term.match_str(address.encoded().as_bytes())
+        }
+        Node::Words(words) => words.find(address.encoded()).is_some(),
+        Node::Bytes(bytes) => bytes.matches(key),
+        Node::Range(range) => range.matches(key),
+        Node::Fuzzy(term) => term.find(address.encoded()).is_some(),
+        Node::And(a, b) => evaluate(a, address) && evaluate(b, address),
+        Node::Or(a, b) => evaluate(a, address) || evaluate(b, address),
+        Node::Not(node) => !evaluate(node, address),
+    }
+}
+
+/// Saves results without private keys as they are, keeping an existing keystore's header.
+/// The file is only readable by the current user.
 pub fn save_results(results: &[VanityResult], output_path: &str) -> Result<()> {
-    let output = serde_json::to_string_pretty(results)?;
-    fs::write(output_path, output)?;
+    let keystore = if is_database(output_path) {
+        store::header(output_path)?
+    } else {
+        load_results_file(output_path)?.1
+    };
+    save_run_results(results, output_path, keystore.as_ref())
+}
+
+/// Saves the results of a search that loaded the keystore header when it opened its output,
+/// so each hit does not re-read everything already stored
+pub fn save_run_results(
+    results: &[VanityResult],
+    output_path: &str,
+    keystore: Option<&KeystoreHeader>,
+) -> Result<()> {
+    save_results_with_header(results, output_path, keystore)?;
     println!("Saved {} results to {}", results.len(), output_path);
     Ok(())
 }
 
-pub fn load_existing_results(output_path: &str) -> Result<Vec<VanityResult>> {
-    if Path::new(output_path).exists() {
-        let content = fs::read_to_string(output_path)?;
-        let results: Vec<VanityResult> = serde_json::from_str(&content)?;
-        Ok(results)
+/// Loads what a search needs from its output: every result of a JSON file, which each hit
+/// rewrites, but only the keystore header of a database, which gets one row per hit
+pub fn open_run_output(output_path: &str) -> Result<(Vec<VanityResult>, Option<KeystoreHeader>)> {
+    if is_database(output_path) {
+        return Ok((Vec::new(), store::header(output_path)?));
+    }
+    load_results_file(output_path)
+}
+
+/// Records a hit of a search opened with `open_run_output` and saves it. A database only gets
+/// the new row, and skips public keys stored by earlier runs through its primary key.
+pub fn save_hit(
+    results: &mut Vec<VanityResult>,
+    result: VanityResult,
+    output_path: &str,
+    keystore: Option<&KeystoreHeader>,
+) -> Result<bool> {
+    if is_database(output_path) {
+        if !insert_hit(&result, output_path, keystore)? {
+            return Ok(false);
+        }
+        results.push(result);
+        return Ok(true);
+    }
+    if !add_result(results, result) {
+        return Ok(false);
+    }
+    save_run_results(results, output_path, keystore)?;
+    Ok(true)
+}
+
+/// Inserts one hit into a database, returning false if its public key is already stored
+pub fn insert_hit(
+    result: &VanityResult,
+    output_path: &str,
+    keystore: Option<&KeystoreHeader>,
+) -> Result<bool> {
+    if keystore.is_some() && !result.private_key.is_empty() {
+        bail!(
+            "{} is an encrypted keystore, add private keys with --encrypt",
+            output_path
+        );
+    }
+    if !store::insert(output_path, result, keystore)? {
+        println!("Skipping {}, already in the results", result.public_key);
+        return Ok(false);
+    }
+    println!("Saved {} to {}", result.public_key, output_path);
+    Ok(true)
+}
+
+/// Replaces the results of a JSON file or database, keeping it an encrypted keystore with
+/// this header if one is given
+pub fn save_results_with_header(
+    results: &[VanityResult],
+    output_path: &str,
+    keystore: Option<&KeystoreHeader>,
+) -> Result<()> {
+    if keystore.is_some() && results.iter().any(|result| !result.private_key.is_empty()) {
+        bail!(
+            "{} is an encrypted keystore, add private keys with --encrypt",
+            output_path
+        );
+    }
+    if is_database(output_path) {
+        return store::save(output_path, results, keystore);
+    }
+    let output = Zeroizing::new(serde_json::to_string_pretty(&ResultsFile::Versioned {
+        schema_version: RESULTS_SCHEMA_VERSION,
+        keystore: keystore.cloned(),
+        results: results.to_vec(),
+    })?);
+    write_private_file(output_path, output.as_bytes())
+}
+
+/// Saves results as an encrypted keystore, sealing every secret that is still in plaintext
+pub fn save_encrypted_results(
+    results: &[VanityResult],
+    output_path: &str,
+    keystore: &Keystore,
+) -> Result<()> {
+    let mut sealed = results.to_vec();
+    for result in &mut sealed {
+        keystore.seal(result)?;
+    }
+    save_results_with_header(&sealed, output_path, Some(keystore.header()))?;
+    println!(
+        "Saved {} results to {} (encrypted)",
+        results.len(),
+        output_path
+    );
+    Ok(())
+}
+
+/// Results of a plain or encrypted results file, with the keystore header if encrypted.
+/// Sealed secrets stay sealed.
+pub fn load_results_file(output_path: &str) -> Result<(Vec<VanityResult>, Option<KeystoreHeader>)> {
+    let (results, header) = read_results_file(output_path)?;
+    let total = results.len();
+    let mut seen = HashSet::with_capacity(total);
+    let unique: Vec<VanityResult> = results
+        .into_iter()
+        .filter(|result| seen.insert(result.public_key.clone()))
+        .collect();
+    if unique.len() < total {
+        eprintln!(
+            "⚠️  Ignoring {} duplicate public keys in {}",
+            total - unique.len(),
+            output_path
+        );
+    }
+    Ok((unique, header))
+}
+
+/// Every result of a results file as stored, duplicates included
+pub fn read_results_file(output_path: &str) -> Result<(Vec<VanityResult>, Option<KeystoreHeader>)> {
+    if is_database(output_path) {
+        return store::load(output_path);
+    }
+    if !Path::new(output_path).exists() {
+        return Ok((Vec::new(), None));
+    }
+    warn_if_world_readable(output_path);
+    let content = Zeroizing::new(fs::read_to_string(output_path)?);
+    // Checked before the full parse, since a newer layout may not parse at all
+    if let Ok(SchemaProbe {
+        schema_version: Some(version),
+    }) = serde_json::from_str(&content)
+    {
+        if version > RESULTS_SCHEMA_VERSION {
+            bail!(
+                "{} uses results schema version {}, this build reads up to {}; upgrade to load it",
+                output_path,
+                version,
+                RESULTS_SCHEMA_VERSION
+            );
+        }
+    }
+    let (results, header) = match serde_json::from_str(&content) {
+        Ok(ResultsFile::Versioned {
+            keystore, results, ..
+        }) => (results, keystore),
+        // Version 1 layouts are rewritten as version 2 by the next save
+        Ok(ResultsFile::V1Plain(results)) => (results, None),
+        Ok(ResultsFile::V1Encrypted { keystore, results }) => (results, Some(keystore)),
+        Err(e) => {
+            let Some((results, header)) = recover_results(&content) else {
+                bail!(
+                    "{} is corrupt and nothing could be recovered: {}",
+                    output_path,
+                    e
+                );
+            };
+            // Keep the damaged original before a save replaces it
+            let backup = format!("{}.corrupt", output_path);
+            if !Path::new(&backup).exists() {
+                write_private_file(&backup, content.as_bytes())?;
+            }
+            // Saves re-read the file for its header until the first one replaces it
+            static WARNED: Once = Once::new();
+            WARNED.call_once(|| {
+                eprintln!(
+                    "⚠️  {} is damaged ({}), recovered {} complete results; the original is kept in {}",
+                    output_path,
+                    e,
+                    results.len(),
+                    backup
+                )
+            });
+            (results, header)
+        }
+    };
+
+    Ok((results, header))
+}
+
+/// The complete records at the start of a results file cut short by a crash in the middle
+/// of a write, with the keystore header of an encrypted file
+fn recover_results(content: &str) -> Option<(Vec<VanityResult>, Option<KeystoreHeader>)> {
+    let text = content.trim_start();
+    if let Some(array) = text.strip_prefix('[') {
+        return Some((recover_array(array), None));
+    }
+    // Object members before `results`, which every layout writes last
+    let mut rest = text.strip_prefix('{')?;
+    let mut header = None;
+    loop {
+        let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<String>();
+        let key = stream.next()?.ok()?;
+        let value = rest[stream.byte_offset()..]
+            .trim_start()
+            .strip_prefix(':')?;
+        match key.as_str() {
+            "results" => {
+                let array = value.trim_start().strip_prefix('[')?;
+                return Some((recover_array(array), header));
+            }
+            "keystore" => {
+                let mut stream =
+                    serde_json::Deserializer::from_str(value).into_iter::<KeystoreHeader>();
+                header = Some(stream.next()?.ok()?);
+                rest = &value[stream.byte_offset()..];
+            }
+            _ => {
+                let mut stream =
+                    serde_json::Deserializer::from_str(value).into_iter::<serde::de::IgnoredAny>();
+                stream.next()?.ok()?;
+                rest = &value[stream.byte_offset()..];
+            }
+        }
+        rest = rest.trim_start().strip_prefix(',')?;
+    }
+}
+
+/// Results of a JSON array up to the first incomplete one
+fn recover_array(mut rest: &str) -> Vec<VanityResult> {
+    let mut results = Vec::new();
+    loop {
+        let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<VanityResult>();
+        match stream.next() {
+            Some(Ok(result)) => results.push(result),
+            _ => return results,
+        }
+        match rest[stream.byte_offset()..].trim_start().strip_prefix(',') {
+            Some(next) => rest = next,
+            None => return results,
+        }
+    }
+}
+
+fn contains_public_key(results: &[VanityResult], public_key: &str) -> bool {
+    results.iter().any(|result| result.public_key == public_key)
+}
+
+/// Records a hit unless its public key is already in the results, as when a deterministic
+/// search such as a derivation path or PDA scan is run again
+pub fn add_result(results: &mut Vec<VanityResult>, result: VanityResult) -> bool {
+    if contains_public_key(results, &result.public_key) {
+        println!("Skipping {}, already in the results", result.public_key);
+        return false;
+    }
+    results.push(result);
+    true
+}
+
+/// A secret for the console, where it is only shown on request
+pub fn reveal(secret: &Secret, show_secrets: bool) -> &str {
+    if show_secrets {
+        secret.expose()
     } else {
-        Ok(Vec::new())
+        REDACTED
     }
 }
 
-pub fn generate_random_seeds(count: usize) -> Vec<u32> {
-    let mut rng = rand::thread_rng();
-    (0..count).map(|_| rng.gen()).collect()
+/// Prints the results, with private keys, mnemonics and BIP39 passphrases redacted
+/// unless `show_secrets` is set
+pub fn display_results(results: &[VanityResult], show_secrets: bool) {
+    println!("\n📋 Found {} vanity addresses:", results.len());
+    println!("{}", "=".repeat(80));
+
+    for (i, result) in results.iter().enumerate() {
+        println!("{}. Public Key: {}", i + 1, result.public_key);
+        if let (Some(chain), Some(address)) = (&result.chain, &result.chain_address) {
+            println!("   {} Address: {}", chain, address);
+        }
+        if let Some(index) = result.derivation_index {
+            println!("   Derivation Index: {}", index);
+        } else if !result.private_key.is_empty() {
+            println!(
+                "   Private Key: {}",
+                reveal(&result.private_key, show_secrets)
+            );
+        } else if result.sealed_secret.is_some() {
+            println!("   Private Key: (encrypted, use unlock)");
+        } else if result.age_secret.is_some() {
+            println!("   Private Key: (encrypted to age recipient, decrypt with age -d)");
+        }
+        if let Some(mnemonic) = &result.mnemonic {
+            println!("   Mnemonic: {}", reveal(mnemonic, show_secrets));
+        }
+        if let Some(path) = &result.derivation_path {
+            println!("   Derivation Path: {}", path);
+        }
+        if let Some(passphrase) = &result.bip39_passphrase {
+            println!("   BIP39 Passphrase: {}", reveal(passphrase, show_secrets));
+        }
+        if let (Some(base), Some(seed), Some(owner)) = (&result.base, &result.seed, &result.owner) {
+            println!("   Base: {}", base);
+            println!("   Seed: {}", seed);
+            println!("   Owner: {}", owner);
+        }
+        if let (Some(program_id), Some(seeds), Some(bump)) =
+            (&result.program_id, &result.seeds, result.bump)
+        {
+            println!("   Program ID: {}", program_id);
+            println!("   Seeds: [{}]", seeds.join(", "));
+            println!("   Bump: {}", bump);
+        }
+        if let (Some(mint), Some(token_program), Some(address)) = (
+            &result.mint,
+            &result.token_program,
+            &result.associated_token_account,
+        ) {
+            println!("   Mint: {}", mint);
+            println!("   Token Program: {}", token_program);
+            println!("   Associated Token Account: {}", address);
+        }
+        if let Some(word) = &result.matched_word {
+            println!("   Word: {}", word);
+        }
+        if let (Some(text), Some(distance)) = (&result.fuzzy_text, result.edit_distance) {
+            println!("   Fuzzy Match: {} (distance {})", text, distance);
+        }
+        if let Some(elapsed) = result.elapsed_secs {
+            println!("   Elapsed: {:.2}s", elapsed);
+        }
+        if let Some(score) = result.score {
+            println!("   Score: {:.2}", score);
+        }
+        println!("   Pattern: {}", result.pattern_matched);
+        if let Some(provenance) = &result.provenance {
+            match &provenance.device {
+                Some(device) => println!(
+                    "   Run: {} ({}, {})",
+                    provenance.run_id, provenance.backend, device
+                ),
+                None => println!("   Run: {} ({})", provenance.run_id, provenance.backend),
+            }
+        }
+        if let Some(label) = &result.label {
+            println!("   Label: {}", label);
+        }
+        if let Some(notes) = &result.notes {
+            println!("   Notes: {}", notes);
+        }
+        println!("   Attempts: {}", format_attempts(result.attempts));
+        println!(
+            "   Found: {}",
+            result.found_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        println!();
+    }
 }
 
 pub fn format_attempts(attempts: u64) -> String {
@@ -104,35 +908,18 @@ pub fn format_attempts(attempts: u64) -> String {
     }
 }
 
-pub fn calculate_probability(starts_with: &Option<String>, ends_with: &Option<String>) -> f64 {
-    let mut total_length = 0;
-
-    if let Some(pattern) = starts_with {
-        total_length += pattern.len();
-    }
-
-    if let Some(pattern) = ends_with {
-        total_length += pattern.len();
-    }
-
-    if total_length == 0 {
-        return 1.0;
-    }
-
-    // Base58 alphabet has 58 characters
-    let base58_chars: f64 = 58.0;
-
-    // Probability of matching a specific pattern
-    let probability = 1.0 / base58_chars.powi(total_length as i32);
+pub fn calculate_probability(pattern: &Pattern) -> f64 {
+    pattern.probability()
+}
 
-    probability
+/// Difficulty in plain keypair generations, accounting for the per-attempt cost
+pub fn estimate_work_needed(pattern: &Pattern, seed_derivation: &SeedDerivation) -> u64 {
+    (estimate_attempts_needed(pattern) as f64 * seed_derivation.cost_factor()) as u64
 }
 
-pub fn estimate_attempts_needed(starts_with: &Option<String>, ends_with: &Option<String>) -> u64 {
-    let probability = calculate_probability(starts_with, ends_with);
+pub fn estimate_attempts_needed(pattern: &Pattern) -> u64 {
+    let probability = calculate_probability(pattern);
 
     // For 50% chance of finding a match
-    let attempts = (0.693 / probability) as u64;
-
-    attempts
+    (0.693 / probability) as u64
 }
@@ -1,90 +1,537 @@
-use anyhow::{anyhow, Result};
-use bs58;
-use crossbeam_channel::{bounded, Receiver, Sender};
+use anyhow::{bail, Result};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::prelude::StdRng;
-use rand::{Rng, SeedableRng};
+use rand::Rng;
 use rayon::prelude::*;
-use solana_sdk::{
-    pubkey::Pubkey,
-    signature::{Keypair, Signer},
-};
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+use solana_sdk::signer::keypair::keypair_from_seed_and_derivation_path;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use zeroize::Zeroizing;
 
+use crate::associated_token::{AssociatedToken, MatchTarget};
+use crate::export::{export_destination, export_results, ExportFormat};
+use crate::keystore::{Keystore, KeystoreHeader};
 use crate::opencl::OpenCLManager;
+use crate::pattern::Pattern;
+use crate::recipient::AgeRecipients;
+use crate::score::{Budget, ScoreConfig, TopN};
+use crate::secret::Secret;
+use crate::store::{self, is_database};
 use crate::utils::{
-    check_pattern_match, estimate_attempts_needed, format_attempts, generate_keypair_from_seed,
-    generate_random_seeds, load_existing_results, save_results, VanityResult,
+    add_result, calculate_probability, check_pattern_match, derive_hd_keypair, display_results,
+    estimate_attempts_needed, estimate_work_needed, format_attempts, generate_keypair_from_seed,
+    generate_mnemonic, insert_hit, keypair_from_mnemonic, new_run_id, open_run_output,
+    save_encrypted_results, save_run_results, solana_derivation_path, wallet_path_at, Provenance,
+    ResultsLock, SeedDerivation, VanityResult,
 };
+use crate::verify::confirm_result;
 
 pub struct VanityGenerator {
-    starts_with: Option<String>,
-    ends_with: Option<String>,
+    pattern: Arc<Pattern>,
     count: usize,
     device: Option<usize>,
     iteration_bits: u32,
-    case_sensitive: bool,
     output_path: String,
     opencl_manager: Option<OpenCLManager>,
+    seed_derivation: SeedDerivation,
+    associated_token: Option<AssociatedToken>,
+    score: Option<Arc<ScoreConfig>>,
+    progressive: Option<(String, Budget)>,
+    format: ExportFormat,
+    keystore: Option<Keystore>,
+    /// Header of the output if it already is an encrypted keystore
+    existing_keystore: Option<KeystoreHeader>,
+    recipients: Option<Arc<AgeRecipients>>,
+    show_secrets: bool,
+    next_index: Arc<AtomicU64>,
     results: Arc<Mutex<Vec<VanityResult>>>,
     total_attempts: Arc<Mutex<u64>>,
+    stop: Arc<AtomicBool>,
+    run_id: String,
+    _lock: ResultsLock,
+}
+
+/// Everything a worker needs to decide whether a candidate is a hit
+#[derive(Clone)]
+struct SearchCriteria {
+    pattern: Arc<Pattern>,
+    associated_token: Option<AssociatedToken>,
+    recipients: Option<Arc<AgeRecipients>>,
+    provenance: Provenance,
+    /// Re-derives hierarchical hits when confirming them
+    master_secret: Option<Zeroizing<[u8; 32]>>,
+}
+
+/// A generated keypair plus whatever is needed to reproduce it
+struct Candidate {
+    keypair: Keypair,
+    derivation_index: Option<u64>,
+    mnemonic: Option<Secret>,
+    derivation_path: Option<String>,
+    bip39_passphrase: Option<Secret>,
+}
+
+impl Candidate {
+    fn plain(keypair: Keypair) -> Self {
+        Candidate {
+            keypair,
+            derivation_index: None,
+            mnemonic: None,
+            derivation_path: None,
+            bip39_passphrase: None,
+        }
+    }
+}
+
+/// Number of leading characters of `address` equal to `target`
+pub fn matching_prefix_len(address: &str, target: &str, case_sensitive: bool) -> usize {
+    address
+        .bytes()
+        .zip(target.bytes())
+        .take_while(|(a, t)| {
+            if case_sensitive {
+                a == t
+            } else {
+                a.eq_ignore_ascii_case(t)
+            }
+        })
+        .count()
+}
+
+/// Candidate at a fixed position of an enumerable keyspace, or None past its end
+fn indexed_candidate(seed_derivation: &SeedDerivation, index: u64) -> Option<Candidate> {
+    match seed_derivation {
+        SeedDerivation::Hierarchical { master_secret, .. } => Some(Candidate {
+            derivation_index: Some(index),
+            ..Candidate::plain(derive_hd_keypair(master_secret, index))
+        }),
+        SeedDerivation::WalletPaths {
+            wallets, changes, ..
+        } => {
+            if index >= seed_derivation.keyspace_size()? {
+                return None;
+            }
+            let (wallet, account, change) = wallet_path_at(index, wallets.len(), *changes);
+            let wallet = &wallets[wallet];
+            let path = DerivationPath::new_bip44(Some(account), Some(change));
+            let keypair =
+                keypair_from_seed_and_derivation_path(&wallet.seed, Some(path.clone())).ok()?;
+            Some(Candidate {
+                derivation_path: Some(format!("{:?}", path)),
+                bip39_passphrase: Some(&wallet.passphrase)
+                    .filter(|passphrase| !passphrase.is_empty())
+                    .cloned(),
+                ..Candidate::plain(keypair)
+            })
+        }
+        SeedDerivation::Random | SeedDerivation::Mnemonic { .. } => None,
+    }
+}
+
+/// Produces a single candidate, or None once a finite keyspace is exhausted
+fn generate_candidate(
+    seed_derivation: &SeedDerivation,
+    next_index: &AtomicU64,
+) -> Option<Candidate> {
+    match seed_derivation {
+        SeedDerivation::Random => Some(Candidate::plain(generate_keypair_from_seed(
+            rand::thread_rng().gen(),
+        ))),
+        SeedDerivation::Mnemonic {
+            word_count,
+            passphrase,
+        } => {
+            let phrase = generate_mnemonic(*word_count).expect("word count validated by the CLI");
+            let path = solana_derivation_path();
+            let keypair = keypair_from_mnemonic(&phrase, passphrase.expose(), &path)
+                .expect("freshly generated mnemonic always derives");
+            Some(Candidate {
+                mnemonic: Some(Secret::new(phrase)),
+                derivation_path: Some(format!("{:?}", path)),
+                bip39_passphrase: Some(passphrase)
+                    .filter(|passphrase| !passphrase.is_empty())
+                    .cloned(),
+                ..Candidate::plain(keypair)
+            })
+        }
+        _ => indexed_candidate(seed_derivation, next_index.fetch_add(1, Ordering::Relaxed)),
+    }
+}
+
+/// Builds a result if the candidate matches the requested patterns
+fn match_candidate(candidate: Candidate, criteria: &SearchCriteria) -> Option<VanityResult> {
+    let matches = |address: &Pubkey| check_pattern_match(address, &criteria.pattern);
+
+    let pubkey = candidate.keypair.pubkey();
+    let associated_token_account = match &criteria.associated_token {
+        None => {
+            if !matches(&pubkey) {
+                return None;
+            }
+            None
+        }
+        Some(associated_token) => {
+            // Check the owner first so owner misses skip the PDA derivation
+            if associated_token.target != MatchTarget::Ata && !matches(&pubkey) {
+                return None;
+            }
+            let address = associated_token.address(&pubkey);
+            if associated_token.target != MatchTarget::Owner && !matches(&address) {
+                return None;
+            }
+            Some(address)
+        }
+    };
+
+    let pattern_matched = criteria.pattern.label().to_string();
+    let matched_address = match (&criteria.associated_token, associated_token_account) {
+        (Some(associated_token), Some(address))
+            if associated_token.target != MatchTarget::Owner =>
+        {
+            address
+        }
+        _ => pubkey,
+    };
+
+    let fuzzy_match = criteria.pattern.fuzzy_match(&matched_address.to_string());
+    // Spans are found in the address body, so shift them past the chain's fixed prefix
+    let format = criteria.pattern.format();
+    let offset = format.fixed_prefix().len();
+    let match_positions = criteria
+        .pattern
+        .matched_spans(&format.body(&matched_address.to_bytes()))
+        .into_iter()
+        .map(|span| span.start + offset..span.end + offset)
+        .collect();
+    let chain_address = criteria.pattern.chain_address(&pubkey.to_bytes());
+
+    // Hierarchical results are re-derived from the master secret, so the key is not stored
+    let private_key = if candidate.derivation_index.is_some() {
+        Secret::default()
+    } else {
+        let bytes = Zeroizing::new(candidate.keypair.to_bytes());
+        Secret::new(bs58::encode(&bytes[..]).into_string())
+    };
+
+    let result = VanityResult {
+        public_key: pubkey.to_string(),
+        private_key,
+        pattern_matched,
+        attempts: 0,
+        found_at: chrono::Utc::now(),
+        derivation_index: candidate.derivation_index,
+        mnemonic: candidate.mnemonic,
+        derivation_path: candidate.derivation_path,
+        bip39_passphrase: candidate.bip39_passphrase,
+        mint: criteria
+            .associated_token
+            .as_ref()
+            .map(|associated_token| associated_token.mint.to_string()),
+        token_program: criteria
+            .associated_token
+            .as_ref()
+            .map(|associated_token| associated_token.token_program.to_string()),
+        matched_word: criteria.pattern.matched_word(&matched_address.to_string()),
+        fuzzy_text: fuzzy_match.as_ref().map(|matched| matched.text.clone()),
+        edit_distance: fuzzy_match.map(|matched| matched.distance),
+        associated_token_account: associated_token_account.map(|address| address.to_string()),
+        chain: chain_address
+            .as_ref()
+            .map(|_| criteria.pattern.format().name().to_string()),
+        chain_address,
+        pattern: Some(criteria.pattern.to_string()),
+        match_positions: Some(match_positions),
+        provenance: Some(criteria.provenance.clone()),
+        ..Default::default()
+    };
+
+    Some(result)
+}
+
+/// Checks a hit the way `verify` will and encrypts it for `--recipient`, so a result that is
+/// persisted always verifies and its plaintext never reaches the results list or the disk
+fn confirm_hit(mut result: VanityResult, criteria: &SearchCriteria) -> Option<VanityResult> {
+    if !confirm_result(&result, criteria.master_secret.as_deref()) {
+        return None;
+    }
+    if let Some(recipients) = &criteria.recipients {
+        if let Err(e) = recipients.seal(&mut result) {
+            eprintln!(
+                "⚠️  Dropping {}, encryption failed: {}",
+                result.public_key, e
+            );
+            return None;
+        }
+    }
+    Some(result)
+}
+
+/// Generates and checks one batch of candidates on the rayon pool,
+/// returning None once a finite keyspace is exhausted
+fn search_batch(
+    seed_derivation: &SeedDerivation,
+    next_index: &AtomicU64,
+    batch_size: usize,
+    criteria: &SearchCriteria,
+) -> Option<Vec<VanityResult>> {
+    match seed_derivation {
+        SeedDerivation::Random | SeedDerivation::Mnemonic { .. } => Some(
+            (0..batch_size)
+                .into_par_iter()
+                .filter_map(|_| {
+                    let candidate = generate_candidate(seed_derivation, next_index)?;
+                    confirm_hit(match_candidate(candidate, criteria)?, criteria)
+                })
+                .collect(),
+        ),
+        _ => {
+            // Claim the whole range up front instead of contending on the counter
+            let start = next_index.fetch_add(batch_size as u64, Ordering::Relaxed);
+            if seed_derivation
+                .keyspace_size()
+                .is_some_and(|size| start >= size)
+            {
+                return None;
+            }
+            Some(
+                (start..start + batch_size as u64)
+                    .into_par_iter()
+                    .filter_map(|index| {
+                        let candidate = indexed_candidate(seed_derivation, index)?;
+                        confirm_hit(match_candidate(candidate, criteria)?, criteria)
+                    })
+                    .collect(),
+            )
+        }
+    }
 }
 
 impl VanityGenerator {
     pub fn new(
-        starts_with: Option<String>,
-        ends_with: Option<String>,
+        pattern: Pattern,
         count: usize,
         device: Option<usize>,
         iteration_bits: u32,
-        case_sensitive: bool,
         output_path: String,
     ) -> Result<Self> {
         let opencl_manager = OpenCLManager::new().ok();
 
         // Load existing results
-        let existing_results = load_existing_results(&output_path).unwrap_or_default();
+        let lock = ResultsLock::acquire(&output_path)?;
+        let (existing_results, existing_keystore) = open_run_output(&output_path)?;
         let results = Arc::new(Mutex::new(existing_results));
         let total_attempts = Arc::new(Mutex::new(0u64));
 
         Ok(VanityGenerator {
-            starts_with,
-            ends_with,
+            pattern: Arc::new(pattern),
             count,
             device,
             iteration_bits,
-            case_sensitive,
             output_path,
             opencl_manager,
+            seed_derivation: SeedDerivation::Random,
+            associated_token: None,
+            score: None,
+            progressive: None,
+            format: ExportFormat::Json,
+            keystore: None,
+            existing_keystore,
+            recipients: None,
+            show_secrets: false,
+            next_index: Arc::new(AtomicU64::new(0)),
             results,
             total_attempts,
+            stop: Arc::new(AtomicBool::new(false)),
+            run_id: new_run_id(),
+            _lock: lock,
         })
     }
 
+    pub fn with_seed_derivation(mut self, seed_derivation: SeedDerivation) -> Self {
+        if let SeedDerivation::Hierarchical { start_index, .. } = seed_derivation {
+            self.next_index = Arc::new(AtomicU64::new(start_index));
+        }
+        self.seed_derivation = seed_derivation;
+        self
+    }
+
+    /// Also writes every found key in this format next to the output file
+    pub fn with_format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Encrypts the secrets in the output file with this keystore
+    pub fn with_keystore(mut self, keystore: Keystore) -> Self {
+        self.keystore = Some(keystore);
+        self
+    }
+
+    /// Encrypts the secrets of every hit to these age recipients as soon as it is found
+    pub fn with_recipients(mut self, recipients: AgeRecipients) -> Self {
+        self.recipients = Some(Arc::new(recipients));
+        self
+    }
+
+    /// Prints private keys and mnemonics in the summary instead of redacting them
+    pub fn with_show_secrets(mut self, show_secrets: bool) -> Self {
+        self.show_secrets = show_secrets;
+        self
+    }
+
+    /// Derives the associated token account of every candidate owner
+    pub fn with_associated_token(mut self, associated_token: AssociatedToken) -> Self {
+        self.associated_token = Some(associated_token);
+        self
+    }
+
+    /// Keeps the best-scoring addresses within a budget instead of exact matches
+    pub fn with_scoring(mut self, score: ScoreConfig) -> Self {
+        self.score = Some(Arc::new(score));
+        self
+    }
+
+    /// Records each longer prefix of `target` as it is reached
+    pub fn with_progressive(mut self, target: String, budget: Budget) -> Self {
+        self.progressive = Some((target, budget));
+        self
+    }
+
+    fn criteria(&self) -> SearchCriteria {
+        SearchCriteria {
+            pattern: Arc::clone(&self.pattern),
+            associated_token: self.associated_token.clone(),
+            recipients: self.recipients.clone(),
+            provenance: Provenance::new(
+                &self.run_id,
+                "cpu",
+                self.seed_derivation.name(),
+                self.pattern.case_sensitive(),
+            ),
+            master_secret: match &self.seed_derivation {
+                SeedDerivation::Hierarchical { master_secret, .. } => Some(master_secret.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    /// One attempt as a CPU worker makes it: generate a candidate, match and confirm it
+    pub fn attempt(&self) -> Option<VanityResult> {
+        let criteria = self.criteria();
+        let candidate = generate_candidate(&self.seed_derivation, &self.next_index)?;
+        confirm_hit(match_candidate(candidate, &criteria)?, &criteria)
+    }
+
     pub async fn run(&mut self) -> Result<()> {
+        // Fail before searching rather than when the first key cannot be saved
+        if self.keystore.is_none() && self.existing_keystore.is_some() {
+            bail!(
+                "{} is an encrypted keystore, pass --encrypt to add keys to it",
+                self.output_path
+            );
+        }
+
         println!("🚀 Starting Solana vanity address generator");
-        println!(
-            "Pattern: starts_with={:?}, ends_with={:?}",
-            self.starts_with, self.ends_with
-        );
-        println!("Target count: {}", self.count);
-        println!("Case sensitive: {}", self.case_sensitive);
+        println!("Pattern: {}", self.pattern);
+        if !self.pattern.format().is_solana() {
+            println!("Chain: {}", self.pattern.format().name());
+        }
+        if self.score.is_none() && self.progressive.is_none() {
+            println!("Target count: {}", self.count);
+        }
+        println!("Case sensitive: {}", self.pattern.case_sensitive());
         println!("Iteration bits: {}", self.iteration_bits);
+        if let Some(associated_token) = &self.associated_token {
+            println!(
+                "Associated token account: mint {}, token program {}, matching {:?}",
+                associated_token.mint, associated_token.token_program, associated_token.target
+            );
+        }
+        match &self.seed_derivation {
+            SeedDerivation::Random => {}
+            SeedDerivation::Hierarchical { start_index, .. } => {
+                println!("Seed derivation: hierarchical from index {}", start_index);
+            }
+            SeedDerivation::Mnemonic { word_count, .. } => {
+                println!(
+                    "Seed derivation: {}-word BIP39 mnemonic at {:?}",
+                    word_count,
+                    solana_derivation_path()
+                );
+            }
+            SeedDerivation::WalletPaths {
+                wallets,
+                accounts,
+                changes,
+            } => {
+                let path = if *changes > 1 {
+                    "m/44'/501'/i'/j'"
+                } else {
+                    "m/44'/501'/i'/0'"
+                };
+                println!(
+                    "Seed derivation: {} over {} accounts x {} change indexes x {} passphrase(s)",
+                    path,
+                    accounts,
+                    changes,
+                    wallets.len()
+                );
+            }
+        }
 
-        if let Some(device) = self.device {
+        if let Some(score) = &self.score {
+            println!(
+                "Score mode: keeping the top {} by '{}' ({})",
+                score.top, score.scorer, score.budget
+            );
+        }
+        if let Some((target, budget)) = &self.progressive {
+            println!("Progressive mode: target '{}' ({})", target, budget);
+        }
+
+        // OpenCL only generates seeds, which mnemonic grinding cannot use
+        let device = self.device.filter(|_| {
+            self.seed_derivation.supports_opencl()
+                && self.score.is_none()
+                && self.progressive.is_none()
+        });
+        if let Some(device) = device {
             println!("Using OpenCL device: {}", device);
+        } else if self.device.is_some() {
+            println!("OpenCL is not supported for this search mode, using CPU-only mode");
         } else {
             println!("Using CPU-only mode");
         }
 
-        let estimated_attempts = estimate_attempts_needed(&self.starts_with, &self.ends_with);
+        let estimated_attempts = estimate_attempts_needed(&self.pattern);
         println!(
             "Estimated attempts needed: {}",
             format_attempts(estimated_attempts)
         );
+        if let Some(keyspace) = self.seed_derivation.keyspace_size() {
+            let probability = calculate_probability(&self.pattern);
+            println!(
+                "Keyspace: {} paths ({:.2}% chance of at least one match)",
+                format_attempts(keyspace),
+                (1.0 - (1.0 - probability).powf(keyspace as f64)) * 100.0
+            );
+        }
+        if self.seed_derivation.cost_factor() > 1.0 {
+            println!(
+                "Estimated work: {} keypair-equivalents (each attempt costs ~{:.0}x a plain keypair)",
+                format_attempts(estimate_work_needed(
+                    &self.pattern,
+                    &self.seed_derivation
+                )),
+                self.seed_derivation.cost_factor()
+            );
+        }
 
         let start_time = Instant::now();
 
@@ -104,7 +551,9 @@ impl VanityGenerator {
         // }
 
         let remaining_count = self.count; // Always search for the requested count
-        println!("Need to find {} more vanity addresses", remaining_count);
+        if self.score.is_none() && self.progressive.is_none() {
+            println!("Need to find {} more vanity addresses", remaining_count);
+        }
 
         // Create progress bar
         let progress_bar = ProgressBar::new(remaining_count as u64);
@@ -142,7 +591,11 @@ impl VanityGenerator {
         });
 
         // Start the search
-        if let Some(device) = self.device {
+        if let Some(score) = &self.score {
+            self.run_score_search(score).await?;
+        } else if let Some((target, budget)) = &self.progressive {
+            self.run_progressive_search(target, budget).await?;
+        } else if let Some(device) = device {
             if let Some(ref opencl_manager) = self.opencl_manager {
                 self.run_gpu_search(opencl_manager, device, remaining_count, &progress_bar)
                     .await?;
@@ -159,13 +612,25 @@ impl VanityGenerator {
         progress_bar.finish_with_message("Search completed!");
 
         let elapsed = start_time.elapsed();
+        // Workers count whole batches, which can overshoot a finite keyspace
         let total_attempts = *self.total_attempts.lock().unwrap();
+        let total_attempts = self
+            .seed_derivation
+            .keyspace_size()
+            .map_or(total_attempts, |size| total_attempts.min(size));
         let rate = total_attempts as f64 / elapsed.as_secs_f64();
 
         println!("\n🎉 Search completed!");
         println!("Total time: {:.2}s", elapsed.as_secs_f64());
         println!("Total attempts: {}", format_attempts(total_attempts));
         println!("Rate: {:.2} attempts/sec", rate);
+        if let SeedDerivation::Hierarchical { .. } = self.seed_derivation {
+            println!(
+                "Next unused index: {} (resume with --start-index {})",
+                self.next_index.load(Ordering::Relaxed),
+                self.next_index.load(Ordering::Relaxed)
+            );
+        }
 
         self.display_results();
         self.save_results()?;
@@ -174,7 +639,7 @@ impl VanityGenerator {
     }
 
     async fn run_cpu_search(&self, target_count: usize, progress_bar: &ProgressBar) -> Result<()> {
-        let batch_size = 1_000_000; // 1M keypairs per batch
+        let batch_size = self.seed_derivation.batch_size(1_000_000); // 1M keypairs per batch
         let num_threads = num_cpus::get();
 
         println!("Using {} CPU threads", num_threads);
@@ -186,50 +651,21 @@ impl VanityGenerator {
 
         for _ in 0..num_threads {
             let tx = tx.clone();
-            let starts_with = self.starts_with.clone();
-            let ends_with = self.ends_with.clone();
-            let case_sensitive = self.case_sensitive;
+            let criteria = self.criteria();
+            let seed_derivation = self.seed_derivation.clone();
+            let next_index = Arc::clone(&self.next_index);
             let total_attempts = Arc::clone(&self.total_attempts);
+            let stop = Arc::clone(&self.stop);
 
             let handle = thread::spawn(move || {
                 let mut local_attempts = 0u64;
-                let mut rng = rand::thread_rng();
-
-                loop {
-                    // Generate batch of seeds
-                    let seeds: Vec<u32> = (0..batch_size).map(|_| rng.gen()).collect();
-
-                    // Process seeds in parallel
-                    let found_results: Vec<VanityResult> = seeds
-                        .par_iter()
-                        .filter_map(|&seed| {
-                            let keypair = generate_keypair_from_seed(seed);
-                            let pubkey = keypair.pubkey();
-                            if check_pattern_match(
-                                &pubkey,
-                                &starts_with,
-                                &ends_with,
-                                case_sensitive,
-                            ) {
-                                let pattern_matched = if starts_with.is_some() {
-                                    starts_with.as_ref().unwrap().clone()
-                                } else if ends_with.is_some() {
-                                    ends_with.as_ref().unwrap().clone()
-                                } else {
-                                    "random".to_string()
-                                };
-                                Some(VanityResult {
-                                    public_key: pubkey.to_string(),
-                                    private_key: bs58::encode(keypair.to_bytes()).into_string(),
-                                    pattern_matched,
-                                    attempts: 0, // We'll update this below
-                                    found_at: chrono::Utc::now(),
-                                })
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
+
+                while !stop.load(Ordering::Relaxed) {
+                    let Some(found_results) =
+                        search_batch(&seed_derivation, &next_index, batch_size, &criteria)
+                    else {
+                        return; // Keyspace exhausted
+                    };
                     // Send found results
                     for mut result in found_results {
                         // Update attempts for each result
@@ -251,27 +687,289 @@ impl VanityGenerator {
             handles.push(handle);
         }
 
-        // Collect results
+        // Collect results until enough are found or every worker has exhausted the keyspace
+        drop(tx);
         let mut found_count = 0;
         while found_count < target_count {
-            if let Ok(result) = rx.recv() {
-                {
-                    let mut results = self.results.lock().unwrap();
-                    results.push(result);
+            let Ok(result) = rx.recv() else {
+                println!("Keyspace exhausted after {} matches", found_count);
+                break;
+            };
+            // Save immediately
+            if !self.save_hit(result)? {
+                continue;
+            }
+            found_count += 1;
+            progress_bar.inc(1);
+        }
+
+        // Clean up threads
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in handles {
+            let _ = handle.join();
+        }
+
+        Ok(())
+    }
+
+    /// Spawns CPU workers that send every candidate passing the pattern and `keep`,
+    /// which can annotate the result from the address the pattern was checked against
+    fn spawn_filtered_workers<F>(
+        &self,
+        tx: &Sender<VanityResult>,
+        keep: F,
+    ) -> Vec<thread::JoinHandle<()>>
+    where
+        F: Fn(&str, &mut VanityResult) -> bool + Clone + Send + 'static,
+    {
+        let num_threads = num_cpus::get();
+        println!("Using {} CPU threads", num_threads);
+
+        let mut handles = Vec::new();
+        for _ in 0..num_threads {
+            let tx = tx.clone();
+            let keep = keep.clone();
+            let criteria = self.criteria();
+            let seed_derivation = self.seed_derivation.clone();
+            let next_index = Arc::clone(&self.next_index);
+            let total_attempts = Arc::clone(&self.total_attempts);
+            let stop = Arc::clone(&self.stop);
+
+            let handle = thread::spawn(move || {
+                let mut local_attempts = 0u64;
+                let batch_size = seed_derivation.batch_size(10_000); // Small batches keep budgets tight
+
+                while !stop.load(Ordering::Relaxed) {
+                    for _ in 0..batch_size {
+                        local_attempts += 1;
+
+                        let Some(candidate) = generate_candidate(&seed_derivation, &next_index)
+                        else {
+                            return; // Keyspace exhausted
+                        };
+                        let Some(mut result) = match_candidate(candidate, &criteria) else {
+                            continue;
+                        };
+
+                        let address = match &criteria.associated_token {
+                            Some(associated_token)
+                                if associated_token.target != MatchTarget::Owner =>
+                            {
+                                result.associated_token_account.clone()
+                            }
+                            _ => None,
+                        }
+                        .unwrap_or_else(|| result.public_key.clone());
+                        if !keep(&address, &mut result) {
+                            continue;
+                        }
+                        let Some(mut result) = confirm_hit(result, &criteria) else {
+                            continue;
+                        };
+
+                        result.attempts = local_attempts;
+                        if tx.send(result).is_err() {
+                            return; // Channel closed, exit thread
+                        }
+                    }
+
+                    // Update global attempt counter
+                    {
+                        let mut global_attempts = total_attempts.lock().unwrap();
+                        *global_attempts += batch_size as u64;
+                    }
                 }
-                found_count += 1;
-                progress_bar.inc(1);
+            });
 
-                // Save immediately
-                self.save_results()?;
+            handles.push(handle);
+        }
+
+        handles
+    }
+
+    /// Receives the next worker result, or None once the budget is spent or the keyspace is exhausted
+    fn recv_within_budget(
+        &self,
+        rx: &Receiver<VanityResult>,
+        budget: &Budget,
+        start_time: Instant,
+    ) -> Option<VanityResult> {
+        loop {
+            if budget.exhausted(start_time, *self.total_attempts.lock().unwrap()) {
+                return None;
+            }
+            match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(result) => return Some(result),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    println!("Keyspace exhausted");
+                    return None;
+                }
             }
         }
+    }
 
-        // Clean up threads
-        drop(tx);
+    fn stop_workers(&self, handles: Vec<thread::JoinHandle<()>>) {
+        self.stop.store(true, Ordering::Relaxed);
         for handle in handles {
             let _ = handle.join();
         }
+    }
+
+    /// Scores every candidate that passes the pattern and keeps the best `top` of them
+    /// until the time or attempt budget is spent
+    async fn run_score_search(&self, config: &Arc<ScoreConfig>) -> Result<()> {
+        let (tx, rx) = bounded::<VanityResult>(1000);
+
+        // Lowest score still in the list, so workers only send candidates that can enter it
+        let threshold = Arc::new(AtomicU64::new(f64::MIN.to_bits()));
+
+        let handles = {
+            let config = Arc::clone(config);
+            let threshold = Arc::clone(&threshold);
+            self.spawn_filtered_workers(&tx, move |address, result| {
+                let score = config.scorer.score(address);
+                result.score = Some(score);
+                score > f64::from_bits(threshold.load(Ordering::Relaxed))
+            })
+        };
+
+        // Keep the heap until the budget runs out, saving every improvement
+        drop(tx);
+        let mut top = TopN::new(config.top);
+        let existing_count = self.results.lock().unwrap().len();
+        // Rows this run inserted into a database, the only ones an eviction may delete
+        let mut inserted = HashSet::new();
+        let start_time = Instant::now();
+        while let Some(result) = self.recv_within_budget(&rx, &config.budget, start_time) {
+            let entrant = result.clone();
+            if top.offer(result) {
+                threshold.store(top.threshold().to_bits(), Ordering::Relaxed);
+                println!(
+                    "⭐ {} scored {:.2}",
+                    entrant.public_key,
+                    entrant.score.unwrap_or_default()
+                );
+                let evicted: Vec<String> = {
+                    let mut results = self.results.lock().unwrap();
+                    let kept = top.sorted();
+                    let evicted = results[existing_count..]
+                        .iter()
+                        .filter(|result| !kept.iter().any(|k| k.public_key == result.public_key))
+                        .map(|result| result.public_key.clone())
+                        .filter(|public_key| inserted.remove(public_key))
+                        .collect();
+                    results.truncate(existing_count);
+                    results.extend(kept);
+                    evicted
+                };
+
+                // Save immediately, a database only deletes the evicted rows and adds the new one
+                if is_database(&self.output_path) {
+                    store::remove(&self.output_path, &evicted)?;
+                    if self.insert_hit(&entrant)? {
+                        inserted.insert(entrant.public_key);
+                    }
+                }
+                self.save_results()?;
+            }
+        }
+
+        self.stop_workers(handles);
+        Ok(())
+    }
+
+    /// Records every new longest prefix of `target` until the whole target is found
+    /// or the budget is spent, then compares the curve with the estimator
+    async fn run_progressive_search(&self, target: &str, budget: &Budget) -> Result<()> {
+        let (tx, rx) = bounded::<VanityResult>(1000);
+
+        // Longest prefix reached so far, so workers only send improvements
+        let best_len = Arc::new(AtomicUsize::new(0));
+
+        let handles = {
+            let target = target.to_string();
+            let case_sensitive = self.pattern.case_sensitive();
+            let best_len = Arc::clone(&best_len);
+            self.spawn_filtered_workers(&tx, move |address, result| {
+                let len = matching_prefix_len(address, &target, case_sensitive);
+                result.pattern_matched = target[..len].to_string();
+                len > best_len.load(Ordering::Relaxed)
+            })
+        };
+
+        // Save each new length as soon as it is reached
+        drop(tx);
+        let case_sensitive = self.pattern.case_sensitive();
+        let start_time = Instant::now();
+        let mut curve = Vec::new();
+        while let Some(mut result) = self.recv_within_budget(&rx, budget, start_time) {
+            let len = result.pattern_matched.len();
+            if len <= best_len.load(Ordering::Relaxed) {
+                continue; // Another worker got there first
+            }
+            best_len.store(len, Ordering::Relaxed);
+
+            // The global counter only moves per batch, so never report less than this worker did
+            let elapsed = start_time.elapsed().as_secs_f64();
+            result.attempts = result.attempts.max(*self.total_attempts.lock().unwrap());
+            result.elapsed_secs = Some(elapsed);
+            println!(
+                "📈 Reached {}/{} '{}' after {} attempts ({:.2}s): {}",
+                len,
+                target.len(),
+                result.pattern_matched,
+                format_attempts(result.attempts),
+                elapsed,
+                result
+                    .associated_token_account
+                    .as_ref()
+                    .unwrap_or(&result.public_key)
+            );
+            curve.push((len, result.attempts, elapsed));
+
+            // Save immediately
+            self.save_hit(result)?;
+
+            if len == target.len() {
+                break;
+            }
+        }
+
+        self.stop_workers(handles);
+
+        // Observed curve against the estimate for each additional character
+        let elapsed = start_time.elapsed().as_secs_f64();
+        let rate = *self.total_attempts.lock().unwrap() as f64 / elapsed.max(f64::EPSILON);
+        println!(
+            "\n📈 Progress curve (expected values use the observed {:.0} attempts/sec)",
+            rate
+        );
+        println!(
+            "{:>4}  {:<16} {:>12} {:>12} {:>10} {:>10}",
+            "Len", "Prefix", "Attempts", "Expected", "Time", "Expected"
+        );
+        let reached = curve.last().map_or(0, |(len, _, _)| *len);
+        for len in 1..=target.len().min(reached + 1) {
+            let expected = estimate_attempts_needed(&Pattern::from_affixes(
+                Some(target[..len].to_string()),
+                None,
+                case_sensitive,
+            )?);
+            let (attempts, time) = match curve.iter().find(|(reached, _, _)| *reached >= len) {
+                Some((_, attempts, time)) => (format_attempts(*attempts), format!("{:.2}s", time)),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            println!(
+                "{:>4}  {:<16} {:>12} {:>12} {:>10} {:>9.2}s",
+                len,
+                &target[..len],
+                attempts,
+                format_attempts(expected),
+                time,
+                expected as f64 / rate
+            );
+        }
 
         Ok(())
     }
@@ -292,51 +990,59 @@ impl VanityGenerator {
 
         // Spawn GPU worker thread
         let tx_clone = tx.clone();
-        let starts_with = self.starts_with.clone();
-        let ends_with = self.ends_with.clone();
-        let case_sensitive = self.case_sensitive;
+        let mut criteria = self.criteria();
+        criteria.provenance.backend = "opencl".to_string();
+        criteria.provenance.device = opencl_manager.device_name(device_idx);
         let total_attempts = Arc::clone(&self.total_attempts);
+        let stop = Arc::clone(&self.stop);
+
+        let seed_derivation = self.seed_derivation.clone();
+        let next_index = Arc::clone(&self.next_index);
 
         let gpu_handle = thread::spawn(move || {
             let mut local_attempts = 0u64;
 
-            loop {
-                // Generate batch of seeds using GPU
-                if let Ok(seeds) = kernel.generate_seeds(batch_size) {
-                    // Process seeds with CPU for real Ed25519 keypair generation
-                    let found_results: Vec<VanityResult> = seeds
-                        .par_iter()
-                        .filter_map(|&seed| {
-                            let keypair = generate_keypair_from_seed(seed);
-                            let pubkey = keypair.pubkey();
-                            if check_pattern_match(
-                                &pubkey,
-                                &starts_with,
-                                &ends_with,
-                                case_sensitive,
-                            ) {
-                                let pattern_matched = if starts_with.is_some() {
-                                    starts_with.as_ref().unwrap().clone()
-                                } else if ends_with.is_some() {
-                                    ends_with.as_ref().unwrap().clone()
-                                } else {
-                                    "random".to_string()
-                                };
-                                Some(VanityResult {
-                                    public_key: pubkey.to_string(),
-                                    private_key: bs58::encode(keypair.to_bytes()).into_string(),
-                                    pattern_matched,
-                                    attempts: local_attempts,
-                                    found_at: chrono::Utc::now(),
-                                })
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
+            while !stop.load(Ordering::Relaxed) {
+                // Generate batch of seeds using GPU, then derive the Ed25519 keypairs on CPU
+                let found_results: Option<Vec<VanityResult>> = match &seed_derivation {
+                    SeedDerivation::Random => kernel.generate_seeds(batch_size).ok().map(|seeds| {
+                        seeds
+                            .par_iter()
+                            .filter_map(|&seed| {
+                                let candidate = Candidate::plain(generate_keypair_from_seed(seed));
+                                confirm_hit(match_candidate(candidate, &criteria)?, &criteria)
+                            })
+                            .collect()
+                    }),
+                    SeedDerivation::Hierarchical { master_secret, .. } => {
+                        let start = next_index.fetch_add(batch_size as u64, Ordering::Relaxed);
+                        kernel
+                            .derive_hd_seeds(master_secret, start, batch_size)
+                            .ok()
+                            .map(|seeds| {
+                                seeds
+                                    .par_iter()
+                                    .enumerate()
+                                    .filter_map(|(offset, seed)| {
+                                        let candidate = Candidate {
+                                            derivation_index: Some(start + offset as u64),
+                                            ..Candidate::plain(keypair_from_seed(seed).ok()?)
+                                        };
+                                        confirm_hit(
+                                            match_candidate(candidate, &criteria)?,
+                                            &criteria,
+                                        )
+                                    })
+                                    .collect()
+                            })
+                    }
+                    _ => unreachable!("derivation mode never runs on OpenCL"),
+                };
 
+                if let Some(found_results) = found_results {
                     // Send found results
-                    for result in found_results {
+                    for mut result in found_results {
+                        result.attempts = local_attempts;
                         if tx_clone.send(result).is_err() {
                             return; // Channel closed, exit thread
                         }
@@ -354,26 +1060,26 @@ impl VanityGenerator {
         });
 
         // Also spawn CPU workers for additional parallelization
-        let cpu_handles = self.spawn_cpu_workers(&tx, target_count)?;
+        let cpu_handles = self.spawn_cpu_workers(&tx)?;
 
-        // Collect results
+        // Collect results until enough are found or every worker has exhausted the keyspace
+        drop(tx);
         let mut found_count = 0;
         while found_count < target_count {
-            if let Ok(result) = rx.recv() {
-                {
-                    let mut results = self.results.lock().unwrap();
-                    results.push(result);
-                }
-                found_count += 1;
-                progress_bar.inc(1);
-
-                // Save immediately
-                self.save_results()?;
+            let Ok(result) = rx.recv() else {
+                println!("Keyspace exhausted after {} matches", found_count);
+                break;
+            };
+            // Save immediately
+            if !self.save_hit(result)? {
+                continue;
             }
+            found_count += 1;
+            progress_bar.inc(1);
         }
 
         // Clean up threads
-        drop(tx);
+        self.stop.store(true, Ordering::Relaxed);
         let _ = gpu_handle.join();
         for handle in cpu_handles {
             let _ = handle.join();
@@ -382,52 +1088,34 @@ impl VanityGenerator {
         Ok(())
     }
 
-    fn spawn_cpu_workers(
-        &self,
-        tx: &Sender<VanityResult>,
-        target_count: usize,
-    ) -> Result<Vec<thread::JoinHandle<()>>> {
+    fn spawn_cpu_workers(&self, tx: &Sender<VanityResult>) -> Result<Vec<thread::JoinHandle<()>>> {
         let num_cpu_threads = (num_cpus::get() / 2).max(1); // Use half CPU cores for GPU mode
         let mut handles = Vec::new();
 
         for _ in 0..num_cpu_threads {
             let tx = tx.clone();
-            let starts_with = self.starts_with.clone();
-            let ends_with = self.ends_with.clone();
-            let case_sensitive = self.case_sensitive;
+            let criteria = self.criteria();
+            let seed_derivation = self.seed_derivation.clone();
+            let next_index = Arc::clone(&self.next_index);
             let total_attempts = Arc::clone(&self.total_attempts);
+            let stop = Arc::clone(&self.stop);
 
             let handle = thread::spawn(move || {
                 let mut local_attempts = 0u64;
-                let mut rng = rand::thread_rng();
-                let batch_size = 100_000; // Smaller batches for CPU workers
+                let batch_size = seed_derivation.batch_size(100_000); // Smaller batches for CPU workers
 
-                loop {
-                    let seeds: Vec<u32> = (0..batch_size).map(|_| rng.gen()).collect();
-
-                    for &seed in &seeds {
+                while !stop.load(Ordering::Relaxed) {
+                    for _ in 0..batch_size {
                         local_attempts += 1;
 
-                        let keypair = generate_keypair_from_seed(seed);
-                        let pubkey = keypair.pubkey();
-
-                        if check_pattern_match(&pubkey, &starts_with, &ends_with, case_sensitive) {
-                            let pattern_matched = if starts_with.is_some() {
-                                starts_with.as_ref().unwrap().clone()
-                            } else if ends_with.is_some() {
-                                ends_with.as_ref().unwrap().clone()
-                            } else {
-                                "random".to_string()
-                            };
-
-                            let result = VanityResult {
-                                public_key: pubkey.to_string(),
-                                private_key: bs58::encode(keypair.to_bytes()).into_string(),
-                                pattern_matched,
-                                attempts: local_attempts,
-                                found_at: chrono::Utc::now(),
-                            };
-
+                        let Some(candidate) = generate_candidate(&seed_derivation, &next_index)
+                        else {
+                            return; // Keyspace exhausted
+                        };
+                        if let Some(mut result) = match_candidate(candidate, &criteria)
+                            .and_then(|result| confirm_hit(result, &criteria))
+                        {
+                            result.attempts = local_attempts;
                             if tx.send(result).is_err() {
                                 return; // Channel closed, exit thread
                             }
@@ -450,137 +1138,59 @@ impl VanityGenerator {
 
     fn display_results(&self) {
         let results = self.results.lock().unwrap();
-        println!("\n📋 Found {} vanity addresses:", results.len());
-        println!("{}", "=".repeat(80));
-
-        for (i, result) in results.iter().enumerate() {
-            println!("{}. Public Key: {}", i + 1, result.public_key);
-            println!("   Private Key: {}", result.private_key);
-            println!("   Pattern: {}", result.pattern_matched);
-            println!("   Attempts: {}", format_attempts(result.attempts));
-            println!(
-                "   Found: {}",
-                result.found_at.format("%Y-%m-%d %H:%M:%S UTC")
-            );
-            println!();
-        }
+        display_results(&results, self.show_secrets);
     }
 
-    fn save_results(&self) -> Result<()> {
-        let results = self.results.lock().unwrap();
-        save_results(&results, &self.output_path)
+    /// Records and saves a new hit, returning false if its public key is already stored
+    fn save_hit(&self, result: VanityResult) -> Result<bool> {
+        if is_database(&self.output_path) {
+            if !self.insert_hit(&result)? {
+                return Ok(false);
+            }
+            self.results.lock().unwrap().push(result);
+        } else if !add_result(&mut self.results.lock().unwrap(), result) {
+            return Ok(false);
+        }
+        self.save_results()?;
+        Ok(true)
     }
 
-    pub async fn generate_vanity_addresses(
-        &mut self,
-        patterns: &[String],
-        count: usize,
-        device_idx: usize,
-    ) -> Result<Vec<VanityResult>> {
-        let opencl = OpenCLManager::new()?;
-        let mut kernel = opencl.create_vanity_kernel(device_idx)?;
-
-        let mut results = Vec::new();
-        let mut attempts = 0u64;
-        let batch_size = 1024 * 1024; // 1M seeds per batch for maximum throughput
-        let start_time = std::time::Instant::now();
-
-        println!(
-            "Starting vanity address generation with {} patterns...",
-            patterns.len()
-        );
-        println!("Target: {} addresses", count);
-        println!("Batch size: {} seeds", batch_size);
-
-        while results.len() < count {
-            // Generate a large batch of seeds
-            let seeds = kernel.generate_seeds(batch_size)?;
-            attempts += seeds.len() as u64;
-
-            // Process seeds in parallel chunks for better CPU utilization
-            let chunk_size = 10000;
-            let mut chunk_results = Vec::new();
-
-            for chunk in seeds.chunks(chunk_size) {
-                let chunk_results_future = tokio::spawn({
-                    let patterns = patterns.to_vec();
-                    let chunk = chunk.to_vec();
-                    let case_sensitive = self.case_sensitive;
-                    async move {
-                        let mut local_results = Vec::new();
-
-                        for &seed in &chunk {
-                            // Generate Ed25519 keypair from seed
-                            let keypair =
-                                Keypair::from_bytes(&seed.to_le_bytes()).unwrap_or_else(|_| {
-                                    // Fallback: use seed as entropy for keypair generation
-                                    let mut rng = StdRng::seed_from_u64(seed as u64);
-                                    Keypair::new()
-                                });
-
-                            let public_key = keypair.pubkey();
-                            let address = public_key.to_string();
-
-                            // Check all patterns
-                            for pattern in &patterns {
-                                let matches = if case_sensitive {
-                                    address.contains(pattern)
-                                } else {
-                                    address.to_lowercase().contains(&pattern.to_lowercase())
-                                };
-
-                                if matches {
-                                    local_results.push(VanityResult {
-                                        public_key: address,
-                                        private_key: bs58::encode(keypair.to_bytes()).into_string(),
-                                        pattern_matched: pattern.clone(),
-                                        attempts: attempts,
-                                        found_at: chrono::Utc::now(),
-                                    });
-                                    break; // Found a match, no need to check other patterns
-                                }
-                            }
-                        }
-
-                        local_results
-                    }
-                });
-
-                chunk_results.extend(chunk_results_future.await.unwrap_or_default());
+    /// Inserts one hit into the output database, sealed if this run encrypts
+    fn insert_hit(&self, result: &VanityResult) -> Result<bool> {
+        match &self.keystore {
+            Some(keystore) => {
+                let mut sealed = result.clone();
+                keystore.seal(&mut sealed)?;
+                insert_hit(&sealed, &self.output_path, Some(keystore.header()))
             }
+            None => insert_hit(result, &self.output_path, self.existing_keystore.as_ref()),
+        }
+    }
 
-            // Add found results
-            results.extend(chunk_results);
-
-            // Progress update
-            let elapsed = start_time.elapsed();
-            let rate = attempts as f64 / elapsed.as_secs_f64();
+    /// Rewrites a JSON output with every result, a database already holds each hit, and
+    /// exports the results in the requested format
+    fn save_results(&self) -> Result<()> {
+        let results = self.results.lock().unwrap();
+        if !is_database(&self.output_path) {
+            match &self.keystore {
+                Some(keystore) => save_encrypted_results(&results, &self.output_path, keystore)?,
+                None => {
+                    save_run_results(&results, &self.output_path, self.existing_keystore.as_ref())?
+                }
+            }
+        }
+        if self.format != ExportFormat::Json {
+            let master_secret = match &self.seed_derivation {
+                SeedDerivation::Hierarchical { master_secret, .. } => Some(&**master_secret),
+                _ => None,
+            };
+            let destination = export_destination(&self.output_path, self.format);
+            let written = export_results(&results, self.format, &destination, master_secret)?;
             println!(
-                "Found {}/{} addresses | Rate: {:.2} MH/s | Elapsed: {:.1}s",
-                results.len(),
-                count,
-                rate / 1_000_000.0,
-                elapsed.as_secs_f64()
+                "Exported {} keys as {:?} to {}",
+                written, self.format, destination
             );
-
-            // Early exit if we have enough results
-            if results.len() >= count {
-                break;
-            }
         }
-
-        // Trim to exact count
-        results.truncate(count);
-
-        let total_time = start_time.elapsed();
-        let total_rate = attempts as f64 / total_time.as_secs_f64();
-
-        println!("\n=== Generation Complete ===");
-        println!("Total attempts: {}", attempts);
-        println!("Total time: {:.2}s", total_time.as_secs_f64());
-        println!("Average rate: {:.2} MH/s", total_rate / 1_000_000.0);
-        println!("Found {} addresses", results.len());
-
-        Ok(results)
+        Ok(())
     }
 }
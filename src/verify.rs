@@ -0,0 +1,288 @@
+use serde::Serialize;
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, Signer};
+use std::collections::HashMap;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+use crate::associated_token::{AssociatedToken, MatchTarget};
+use crate::chain::Chain;
+use crate::pattern::Pattern;
+use crate::pda::SeedComponent;
+use crate::utils::{
+    check_pattern_match, derive_hd_keypair, keypair_from_mnemonic, solana_derivation_path,
+    VanityResult,
+};
+
+/// Outcome of checking one result, one entry of `verify --json`
+#[derive(Serialize)]
+pub struct Verification {
+    pub public_key: String,
+    pub ok: bool,
+    /// What is wrong with the result
+    pub failures: Vec<String>,
+    /// Checks that could not run, such as hierarchical keys without the master secret
+    pub unchecked: Vec<String>,
+}
+
+/// Re-derives the public key from whatever the result records, checks the derived addresses
+/// and re-matches the recorded pattern spec
+pub fn verify_result(result: &VanityResult, master_secret: Option<&[u8; 32]>) -> Verification {
+    let mut verification = Verification {
+        public_key: result.public_key.clone(),
+        ok: true,
+        failures: Vec::new(),
+        unchecked: Vec::new(),
+    };
+    match Pubkey::from_str(&result.public_key) {
+        Ok(public_key) => {
+            check_key(result, &public_key, master_secret, &mut verification);
+            check_pattern(result, &public_key, &mut verification);
+        }
+        Err(_) => verification
+            .failures
+            .push(format!("{} is not a valid public key", result.public_key)),
+    }
+    verification.ok = verification.failures.is_empty();
+    verification
+}
+
+/// Checks a new hit before a search persists it, so every saved result verifies.
+/// Reports why a hit is dropped.
+pub fn confirm_result(result: &VanityResult, master_secret: Option<&[u8; 32]>) -> bool {
+    let verification = verify_result(result, master_secret);
+    if !verification.ok {
+        eprintln!(
+            "⚠️  Dropping {}, verification failed: {}",
+            result.public_key,
+            verification.failures.join("; ")
+        );
+    }
+    verification.ok
+}
+
+/// Verifies every result and flags public keys that occur more than once
+pub fn verify_results(
+    results: &[VanityResult],
+    master_secret: Option<&[u8; 32]>,
+) -> Vec<Verification> {
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    for result in results {
+        *occurrences.entry(&result.public_key).or_default() += 1;
+    }
+    results
+        .iter()
+        .map(|result| {
+            let mut verification = verify_result(result, master_secret);
+            let count = occurrences[result.public_key.as_str()];
+            if count > 1 {
+                verification
+                    .failures
+                    .push(format!("public key occurs {} times", count));
+                verification.ok = false;
+            }
+            verification
+        })
+        .collect()
+}
+
+fn check_key(
+    result: &VanityResult,
+    public_key: &Pubkey,
+    master_secret: Option<&[u8; 32]>,
+    verification: &mut Verification,
+) {
+    if !result.private_key.is_empty() {
+        let bytes = Zeroizing::new(
+            bs58::decode(result.private_key.expose())
+                .into_vec()
+                .unwrap_or_default(),
+        );
+        let address = (bytes.len() == 64)
+            .then(|| keypair_from_seed(&bytes[..32]).ok())
+            .flatten()
+            .map(|keypair| keypair.pubkey());
+        expect_address(verification, public_key, "private key", address);
+        // The second half of a Solana keypair is its public key
+        if bytes.len() == 64 && bytes[32..] != public_key.to_bytes() {
+            verification
+                .failures
+                .push("public half of the private key differs from the public key".to_string());
+        }
+    }
+    if let Some(mnemonic) = &result.mnemonic {
+        let path = match &result.derivation_path {
+            Some(path) => DerivationPath::from_absolute_path_str(path).ok(),
+            None => Some(solana_derivation_path()),
+        };
+        let passphrase = result
+            .bip39_passphrase
+            .as_ref()
+            .map_or("", |passphrase| passphrase.expose());
+        let address = path.and_then(|path| {
+            keypair_from_mnemonic(mnemonic.expose(), passphrase, &path)
+                .ok()
+                .map(|keypair| keypair.pubkey())
+        });
+        expect_address(verification, public_key, "mnemonic", address);
+    }
+    if let Some(index) = result.derivation_index {
+        match master_secret {
+            Some(master_secret) => expect_address(
+                verification,
+                public_key,
+                "master secret",
+                Some(derive_hd_keypair(master_secret, index).pubkey()),
+            ),
+            None => verification
+                .unchecked
+                .push("hierarchical key needs the master secret".to_string()),
+        }
+    }
+    if let (Some(base), Some(seed), Some(owner)) = (&result.base, &result.seed, &result.owner) {
+        let address = Pubkey::from_str(base)
+            .ok()
+            .zip(Pubkey::from_str(owner).ok())
+            .and_then(|(base, owner)| Pubkey::create_with_seed(&base, seed, &owner).ok());
+        expect_address(verification, public_key, "base, seed and owner", address);
+    }
+    if let (Some(program_id), Some(seeds), Some(bump)) =
+        (&result.program_id, &result.seeds, result.bump)
+    {
+        let seeds: Option<Vec<Vec<u8>>> = seeds
+            .iter()
+            .map(|spec| match SeedComponent::from_str(spec) {
+                Ok(SeedComponent::Fixed { bytes, .. }) => Some(bytes),
+                _ => None,
+            })
+            .collect();
+        let address =
+            Pubkey::from_str(program_id)
+                .ok()
+                .zip(seeds)
+                .and_then(|(program_id, mut seeds)| {
+                    seeds.push(vec![bump]);
+                    let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+                    Pubkey::create_program_address(&seeds, &program_id).ok()
+                });
+        expect_address(
+            verification,
+            public_key,
+            "program ID, seeds and bump",
+            address,
+        );
+    }
+
+    let nothing_recorded = result.private_key.is_empty()
+        && result.mnemonic.is_none()
+        && result.derivation_index.is_none()
+        && result.seed.is_none()
+        && result.bump.is_none();
+    if nothing_recorded {
+        if result.sealed_secret.is_some() {
+            verification
+                .unchecked
+                .push("secret is sealed in the keystore".to_string());
+        } else if result.age_secret.is_some() {
+            verification
+                .unchecked
+                .push("secret is encrypted to an age recipient".to_string());
+        } else {
+            verification
+                .failures
+                .push("no private key or derivation is recorded".to_string());
+        }
+    }
+
+    if let (Some(mint), Some(token_program), Some(account)) = (
+        &result.mint,
+        &result.token_program,
+        &result.associated_token_account,
+    ) {
+        let expected = Pubkey::from_str(mint)
+            .ok()
+            .zip(Pubkey::from_str(token_program).ok())
+            .map(|(mint, token_program)| {
+                AssociatedToken {
+                    mint,
+                    token_program,
+                    target: MatchTarget::Owner,
+                }
+                .address(public_key)
+            });
+        if expected.map(|expected| expected.to_string()).as_ref() != Some(account) {
+            verification.failures.push(format!(
+                "associated token account {} does not belong to the owner and mint",
+                account
+            ));
+        }
+    }
+    if let (Some(chain), Some(address)) = (&result.chain, &result.chain_address) {
+        match Chain::from_str(chain) {
+            Ok(chain) if chain.format().address(&public_key.to_bytes()) == *address => {}
+            Ok(_) => verification.failures.push(format!(
+                "{} address {} does not belong to the public key",
+                chain, address
+            )),
+            Err(e) => verification.failures.push(e.to_string()),
+        }
+    }
+}
+
+/// Records a failure unless `source` decodes to the public key
+fn expect_address(
+    verification: &mut Verification,
+    public_key: &Pubkey,
+    source: &str,
+    address: Option<Pubkey>,
+) {
+    match address {
+        Some(address) if address == *public_key => {}
+        Some(address) => verification.failures.push(format!(
+            "{} gives {} instead of the public key",
+            source, address
+        )),
+        None => verification
+            .failures
+            .push(format!("{} cannot be decoded", source)),
+    }
+}
+
+fn check_pattern(result: &VanityResult, public_key: &Pubkey, verification: &mut Verification) {
+    let (Some(spec), Some(provenance)) = (&result.pattern, &result.provenance) else {
+        verification
+            .unchecked
+            .push("no pattern spec is recorded".to_string());
+        return;
+    };
+    let chain = match result.chain.as_deref().map(Chain::from_str).transpose() {
+        Ok(chain) => chain.unwrap_or(Chain::Solana),
+        Err(e) => return verification.failures.push(e.to_string()),
+    };
+    let pattern = match Pattern::from_spec(spec, provenance.case_sensitive, chain) {
+        Ok(Some(pattern)) => pattern,
+        Ok(None) => {
+            return verification
+                .unchecked
+                .push("pattern spec summarizes options that are not recorded".to_string())
+        }
+        Err(e) => {
+            return verification
+                .failures
+                .push(format!("pattern spec '{}' does not parse: {}", spec, e))
+        }
+    };
+    // Token account searches may have matched the owner or its associated token account
+    let account = result
+        .associated_token_account
+        .as_deref()
+        .and_then(|account| Pubkey::from_str(account).ok());
+    let matches = check_pattern_match(public_key, &pattern)
+        || account.is_some_and(|account| check_pattern_match(&account, &pattern));
+    if !matches {
+        verification
+            .failures
+            .push(format!("address does not match the pattern '{}'", spec));
+    }
+}
@@ -0,0 +1,230 @@
+use aho_corasick::{AhoCorasick, Anchored, Input, MatchKind, StartKind};
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::pattern::BASE58_ALPHABET;
+
+/// Where a dictionary word has to appear in the address
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WordPosition {
+    Prefix,
+    Suffix,
+    Anywhere,
+}
+
+impl FromStr for WordPosition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "prefix" => Ok(WordPosition::Prefix),
+            "suffix" => Ok(WordPosition::Suffix),
+            "anywhere" => Ok(WordPosition::Anywhere),
+            _ => bail!("Word position must be prefix, suffix or anywhere"),
+        }
+    }
+}
+
+/// How the case of dictionary words has to appear in the address
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WordCase {
+    /// Any mix of upper and lower case
+    Any,
+    /// Exactly as written in the file
+    Exact,
+    /// First letter upper case, the rest lower case
+    Capitalized,
+}
+
+impl FromStr for WordCase {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "any" => Ok(WordCase::Any),
+            "exact" => Ok(WordCase::Exact),
+            "capitalized" => Ok(WordCase::Capitalized),
+            _ => bail!("Word case must be any, exact or capitalized"),
+        }
+    }
+}
+
+/// Dictionary words compiled into one Aho-Corasick automaton
+pub struct WordList {
+    automaton: AhoCorasick,
+    words: Vec<String>,
+    position: WordPosition,
+    probability: f64,
+}
+
+impl WordList {
+    pub fn load(
+        path: &str,
+        min_length: usize,
+        position: WordPosition,
+        case: WordCase,
+    ) -> Result<Self> {
+        let mut too_short = 0;
+        let mut invalid = 0;
+        let mut seen = HashSet::new();
+        let mut words = Vec::new();
+
+        for line in fs::read_to_string(path)?.lines() {
+            let word = line.trim();
+            if word.is_empty() || word.starts_with('#') {
+                continue;
+            }
+            if !word.is_ascii() {
+                invalid += 1;
+                continue;
+            }
+            if word.len() < min_length {
+                too_short += 1;
+                continue;
+            }
+            let word = match case {
+                WordCase::Any | WordCase::Exact => word.to_string(),
+                WordCase::Capitalized => {
+                    let lower = word.to_ascii_lowercase();
+                    lower[..1].to_ascii_uppercase() + &lower[1..]
+                }
+            };
+            if !word.bytes().all(|c| base58_variants(c, case) > 0) {
+                invalid += 1;
+                continue;
+            }
+            let key = match case {
+                WordCase::Any => word.to_ascii_lowercase(),
+                _ => word.clone(),
+            };
+            if seen.insert(key) {
+                words.push(word);
+            }
+        }
+
+        println!(
+            "Loaded {} words from {} ({} shorter than {}, {} dropped as not base58)",
+            words.len(),
+            path,
+            too_short,
+            min_length,
+            invalid
+        );
+        if words.is_empty() {
+            bail!("Word list {} has no usable words", path);
+        }
+
+        // Suffixes are found by an anchored search over the reversed address
+        let patterns: Vec<String> = match position {
+            WordPosition::Suffix => words
+                .iter()
+                .map(|word| word.chars().rev().collect())
+                .collect(),
+            _ => words.clone(),
+        };
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(case == WordCase::Any)
+            .match_kind(MatchKind::LeftmostLongest)
+            .start_kind(StartKind::Both)
+            .build(&patterns)?;
+
+        // Chance that none of the words matches, treating them as independent
+        let miss: f64 = words
+            .iter()
+            .map(|word| {
+                let p: f64 = word
+                    .bytes()
+                    .map(|c| base58_variants(c, case) as f64 / BASE58_ALPHABET.len() as f64)
+                    .product();
+                let positions = match position {
+                    WordPosition::Anywhere => 45usize.saturating_sub(word.len()),
+                    _ => 1,
+                };
+                (1.0 - p).powi(positions as i32)
+            })
+            .product();
+
+        println!(
+            "Combined word hit probability: {:.3e} per address",
+            1.0 - miss
+        );
+
+        Ok(WordList {
+            automaton,
+            words,
+            position,
+            probability: 1.0 - miss,
+        })
+    }
+
+    /// Word found at the configured position, preferring the longest
+    pub fn find(&self, address: &str) -> Option<&str> {
+        self.find_span(address).map(|(word, _)| word)
+    }
+
+    /// Word found at the configured position and where it is in the address
+    pub fn find_span(&self, address: &str) -> Option<(&str, Range<usize>)> {
+        let (found, range) = match self.position {
+            WordPosition::Prefix => {
+                let found = self
+                    .automaton
+                    .find(Input::new(address).anchored(Anchored::Yes))?;
+                (found, found.range())
+            }
+            WordPosition::Suffix => {
+                let reversed: String = address.chars().rev().collect();
+                let found = self
+                    .automaton
+                    .find(Input::new(&reversed).anchored(Anchored::Yes))?;
+                (found, address.len() - found.end()..address.len())
+            }
+            WordPosition::Anywhere => {
+                let found = self.automaton.find(address)?;
+                (found, found.range())
+            }
+        };
+        Some((&self.words[found.pattern().as_usize()], range))
+    }
+
+    /// Length of the longest word starting anywhere in the address
+    pub fn longest_match(&self, address: &str) -> usize {
+        (0..address.len())
+            .filter_map(|start| {
+                self.automaton
+                    .find(Input::new(address).range(start..).anchored(Anchored::Yes))
+            })
+            .map(|found| found.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Chance that a random address contains at least one of the words
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn position(&self) -> WordPosition {
+        self.position
+    }
+}
+
+/// Number of base58 characters that can stand for `c` under the case mode
+fn base58_variants(c: u8, case: WordCase) -> usize {
+    let variants = match case {
+        WordCase::Any => vec![c.to_ascii_lowercase(), c.to_ascii_uppercase()],
+        _ => vec![c],
+    };
+    let mut variants: Vec<u8> = variants
+        .into_iter()
+        .filter(|variant| BASE58_ALPHABET.contains(variant))
+        .collect();
+    variants.dedup();
+    variants.len()
+}